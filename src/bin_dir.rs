@@ -0,0 +1,454 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
+use std::io::{Read as _, Seek as _, SeekFrom, Write as _};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::delete_folder;
+
+/// binaries installed by rustup in cargo bin directory which are not managed
+/// by cargo install
+const RUSTUP_PROXIES: [&str; 14] = [
+    "cargo",
+    "cargo-clippy",
+    "cargo-fmt",
+    "cargo-miri",
+    "clippy-driver",
+    "rls",
+    "rust-analyzer",
+    "rust-gdb",
+    "rust-gdbgui",
+    "rust-lldb",
+    "rustc",
+    "rustdoc",
+    "rustfmt",
+    "rustup",
+];
+
+/// Content of .crates.toml file
+#[derive(Default, Deserialize, Serialize)]
+struct CrateListingV1 {
+    #[serde(default)]
+    v1: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Content of .crates2.json file. Unknown fields are kept so file is written
+/// back without losing information added by newer cargo
+#[derive(Default, Deserialize, Serialize)]
+struct CrateListingV2 {
+    #[serde(default)]
+    installs: BTreeMap<String, InstallInfo>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_json::Value>,
+}
+
+/// Install information of single package stored in .crates2.json
+#[derive(Default, Deserialize, Serialize)]
+struct InstallInfo {
+    #[serde(default)]
+    bins: BTreeSet<String>,
+    #[serde(default)]
+    features: BTreeSet<String>,
+    #[serde(default)]
+    profile: Option<String>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_json::Value>,
+}
+
+/// Package installed using cargo install along with binaries installed by it
+pub(crate) struct InstalledPackage {
+    key: String,
+    name: String,
+    version: String,
+    source: String,
+    bins: BTreeSet<String>,
+    features: BTreeSet<String>,
+    profile: Option<String>,
+    missing_bins: BTreeSet<String>,
+    recorded_in_both: bool,
+}
+
+impl InstalledPackage {
+    /// return package id used as key in metadata files
+    pub(crate) fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// return package name
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// return package version
+    pub(crate) fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// return package source
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// return binaries installed by package
+    pub(crate) fn bins(&self) -> &BTreeSet<String> {
+        &self.bins
+    }
+
+    /// return features used while installing package
+    pub(crate) fn features(&self) -> &BTreeSet<String> {
+        &self.features
+    }
+
+    /// return profile used while installing package
+    pub(crate) fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// return recorded binaries which are not present in bin directory
+    pub(crate) fn missing_bins(&self) -> &BTreeSet<String> {
+        &self.missing_bins
+    }
+
+    /// check if package metadata is stale. Metadata is stale when recorded
+    /// binary is missing or package is only recorded in one metadata file
+    pub(crate) fn is_stale(&self) -> bool {
+        !self.missing_bins.is_empty() || !self.recorded_in_both
+    }
+
+    /// check if package matches provided name or name@version spec
+    pub(crate) fn matches(&self, spec: &str) -> bool {
+        match spec.split_once('@') {
+            Some((name, version)) => self.name == name && self.version == version,
+            None => self.name == spec,
+        }
+    }
+}
+
+/// Stores cargo install metadata of .crates.toml and .crates2.json files. Both
+/// files are locked same way as cargo while struct is alive
+pub(crate) struct BinDir {
+    path: PathBuf,
+    v1_file: Option<File>,
+    v2_file: Option<File>,
+    v1: CrateListingV1,
+    v2: CrateListingV2,
+}
+
+impl BinDir {
+    /// load cargo install metadata files
+    pub(crate) fn load(bin_dir: &Path, crates_toml: &Path, crates2_json: &Path) -> Result<Self> {
+        let (v1_file, v1_content) = open_locked(crates_toml)?;
+        let (v2_file, v2_content) = open_locked(crates2_json)?;
+        let v1 = if v1_content.trim().is_empty() {
+            CrateListingV1::default()
+        } else {
+            toml::from_str(&v1_content).context("failed to parse .crates.toml")?
+        };
+        let v2 = if v2_content.trim().is_empty() {
+            CrateListingV2::default()
+        } else {
+            serde_json::from_str(&v2_content).context("failed to parse .crates2.json")?
+        };
+        Ok(Self {
+            path: bin_dir.to_path_buf(),
+            v1_file,
+            v2_file,
+            v1,
+            v2,
+        })
+    }
+
+    /// list all packages recorded in any of metadata files
+    pub(crate) fn packages(&self) -> Vec<InstalledPackage> {
+        let keys = self
+            .v1
+            .v1
+            .keys()
+            .chain(self.v2.installs.keys())
+            .collect::<BTreeSet<_>>();
+        let mut packages = Vec::new();
+        for key in keys {
+            let v1_bins = self.v1.v1.get(key);
+            let install_info = self.v2.installs.get(key);
+            let bins = v1_bins
+                .into_iter()
+                .flatten()
+                .chain(install_info.into_iter().flat_map(|info| &info.bins))
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            let missing_bins = bins
+                .iter()
+                .filter(|bin| !self.path.join(bin).exists())
+                .cloned()
+                .collect();
+            let (name, version, source) = split_package_id(key);
+            packages.push(InstalledPackage {
+                key: key.clone(),
+                name,
+                version,
+                source,
+                bins,
+                features: install_info
+                    .map(|info| info.features.clone())
+                    .unwrap_or_default(),
+                profile: install_info.and_then(|info| info.profile.clone()),
+                missing_bins,
+                recorded_in_both: v1_bins.is_some() && install_info.is_some(),
+            });
+        }
+        packages
+    }
+
+    /// list binaries present in bin directory which are not recorded in
+    /// metadata. Rustup proxies are skipped
+    pub(crate) fn untracked_bins(&self) -> Result<Vec<String>> {
+        let tracked = self
+            .v1
+            .v1
+            .values()
+            .flatten()
+            .chain(self.v2.installs.values().flat_map(|info| &info.bins))
+            .collect::<BTreeSet<_>>();
+        let mut untracked = Vec::new();
+        if self.path.is_dir() {
+            for entry in std::fs::read_dir(&self.path).context("failed to read bin directory")? {
+                let file_name = entry?.file_name();
+                let Some(file_name) = file_name.to_str() else {
+                    continue;
+                };
+                if !tracked.contains(&file_name.to_string()) && !is_rustup_proxy(file_name) {
+                    untracked.push(file_name.to_string());
+                }
+            }
+        }
+        untracked.sort();
+        Ok(untracked)
+    }
+
+    /// uninstall package by removing its binaries and its entry from both
+    /// metadata files same as cargo uninstall
+    pub(crate) fn uninstall(&mut self, package: &InstalledPackage, dry_run: bool) -> Result<()> {
+        for bin in package.bins() {
+            delete_folder(&self.path.join(bin), dry_run)?;
+        }
+        self.v1.v1.remove(package.key());
+        self.v2.installs.remove(package.key());
+        if !dry_run {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// remove stale metadata of package. Missing binaries are removed from
+    /// package and package is removed when no binary is left. Entry of
+    /// .crates2.json is only updated when it exists since cargo cannot parse
+    /// entry without its install information
+    pub(crate) fn prune_stale(&mut self, package: &InstalledPackage, dry_run: bool) -> Result<()> {
+        let key = package.key();
+        let remaining_bins = package
+            .bins()
+            .difference(package.missing_bins())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        if remaining_bins.is_empty() {
+            self.v1.v1.remove(key);
+            self.v2.installs.remove(key);
+        } else {
+            self.v1.v1.insert(key.to_string(), remaining_bins.clone());
+            if let Some(install_info) = self.v2.installs.get_mut(key) {
+                install_info.bins = remaining_bins;
+            }
+        }
+        if !dry_run {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// write both metadata files
+    fn save(&mut self) -> Result<()> {
+        if let Some(file) = &mut self.v1_file {
+            let content =
+                toml::to_string(&self.v1).context("failed to convert .crates.toml to toml")?;
+            write_locked(file, &content).context("failed to write .crates.toml")?;
+        }
+        if let Some(file) = &mut self.v2_file {
+            let content = serde_json::to_string(&self.v2)
+                .context("failed to convert .crates2.json to json")?;
+            write_locked(file, &content).context("failed to write .crates2.json")?;
+        }
+        Ok(())
+    }
+}
+
+/// check if binary name is rustup proxy
+fn is_rustup_proxy(file_name: &str) -> bool {
+    let name = file_name.strip_suffix(".exe").unwrap_or(file_name);
+    RUSTUP_PROXIES.contains(&name)
+}
+
+/// split package id such as `name 1.0.0 (registry+https://...)` into name,
+/// version and source
+fn split_package_id(key: &str) -> (String, String, String) {
+    let mut parts = key.splitn(3, ' ');
+    let name = parts.next().unwrap_or_default().to_string();
+    let version = parts.next().unwrap_or_default().to_string();
+    let source = parts
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .to_string();
+    (name, version, source)
+}
+
+/// open existing file with exclusive lock and return its content
+fn open_locked(path: &Path) -> Result<(Option<File>, String)> {
+    if !path.exists() {
+        return Ok((None, String::new()));
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    file.lock()
+        .with_context(|| format!("failed to lock {}", path.display()))?;
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok((Some(file), content))
+}
+
+/// replace content of locked file
+fn write_locked(file: &mut File, content: &str) -> Result<()> {
+    file.seek(SeekFrom::Start(0))?;
+    file.set_len(0)?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::BinDir;
+
+    const CRATES_TOML: &str = r#"[v1]
+"foo 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)" = ["foo"]
+"bar 0.2.0 (git+https://github.com/example/bar#0123456789abcdef)" = ["bar", "bar-cli"]
+"#;
+
+    const CRATES2_JSON: &str = concat!(
+        r#"{"installs":{"#,
+        r#""foo 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)":"#,
+        r#"{"version_req":null,"bins":["foo"],"features":["cli"],"all_features":false,"#,
+        r#""no_default_features":false,"profile":"release","#,
+        r#""target":"x86_64-unknown-linux-gnu","rustc":"rustc 1.80.0"},"#,
+        r#""bar 0.2.0 (git+https://github.com/example/bar#0123456789abcdef)":"#,
+        r#"{"version_req":null,"bins":["bar","bar-cli"],"features":[],"all_features":false,"#,
+        r#""no_default_features":false,"profile":"release","#,
+        r#""target":"x86_64-unknown-linux-gnu","rustc":"rustc 1.80.0"}}}"#
+    );
+
+    fn fixture_dir() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path();
+        fs::create_dir_all(path.join("bin")).unwrap();
+        fs::write(path.join(".crates.toml"), CRATES_TOML).unwrap();
+        fs::write(path.join(".crates2.json"), CRATES2_JSON).unwrap();
+        for bin in ["foo", "bar", "rustc", "manual"] {
+            fs::write(path.join("bin").join(bin), bin).unwrap();
+        }
+        temp_dir
+    }
+
+    fn load(path: &std::path::Path) -> BinDir {
+        BinDir::load(
+            &path.join("bin"),
+            &path.join(".crates.toml"),
+            &path.join(".crates2.json"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn installed_package_mapping_test() {
+        let temp_dir = fixture_dir();
+        let path = temp_dir.path();
+        let bin_dir = load(path);
+        let packages = bin_dir.packages();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name(), "bar");
+        assert_eq!(packages[0].version(), "0.2.0");
+        assert!(packages[0].is_stale());
+        assert!(packages[0].missing_bins().contains("bar-cli"));
+        assert_eq!(packages[1].name(), "foo");
+        assert_eq!(
+            packages[1].source(),
+            "registry+https://github.com/rust-lang/crates.io-index"
+        );
+        assert_eq!(packages[1].profile(), Some("release"));
+        assert!(packages[1].features().contains("cli"));
+        assert!(!packages[1].is_stale());
+        assert!(packages[1].matches("foo@1.0.0"));
+        assert!(!packages[1].matches("foo@2.0.0"));
+        assert_eq!(bin_dir.untracked_bins().unwrap(), ["manual"]);
+    }
+
+    #[test]
+    fn uninstall_and_prune_test() {
+        let temp_dir = fixture_dir();
+        let path = temp_dir.path();
+        let mut bin_dir = load(path);
+        let packages = bin_dir.packages();
+        bin_dir.prune_stale(&packages[0], false).unwrap();
+        bin_dir.uninstall(&packages[1], false).unwrap();
+        drop(bin_dir);
+        assert!(!path.join("bin/foo").exists());
+        assert!(path.join("bin/bar").exists());
+
+        let crates_toml = fs::read_to_string(path.join(".crates.toml")).unwrap();
+        assert!(!crates_toml.contains("foo"));
+        assert!(!crates_toml.contains("bar-cli"));
+        let crates2_json = fs::read_to_string(path.join(".crates2.json")).unwrap();
+        assert!(!crates2_json.contains("foo"));
+        assert!(!crates2_json.contains("bar-cli"));
+        assert!(crates2_json.contains(r#""target":"x86_64-unknown-linux-gnu""#));
+
+        let packages = load(path).packages();
+        assert_eq!(packages.len(), 1);
+        assert!(!packages[0].is_stale());
+    }
+
+    #[test]
+    fn prune_v1_only_package_test() {
+        let temp_dir = fixture_dir();
+        let path = temp_dir.path();
+        let baz = r#""baz 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)""#;
+        fs::write(
+            path.join(".crates.toml"),
+            format!("{CRATES_TOML}{baz} = [\"baz\", \"manual\"]\n"),
+        )
+        .unwrap();
+        let mut bin_dir = load(path);
+        let packages = bin_dir.packages();
+        assert_eq!(packages[1].name(), "baz");
+        assert!(packages[1].missing_bins().contains("baz"));
+        bin_dir.prune_stale(&packages[1], false).unwrap();
+        drop(bin_dir);
+
+        let crates_toml = fs::read_to_string(path.join(".crates.toml")).unwrap();
+        assert!(crates_toml.contains(r#"= ["manual"]"#));
+        let crates2_json = fs::read_to_string(path.join(".crates2.json")).unwrap();
+        assert!(!crates2_json.contains("baz"));
+        let crates2_json: serde_json::Value = serde_json::from_str(&crates2_json).unwrap();
+        for install_info in crates2_json["installs"].as_object().unwrap().values() {
+            assert!(install_info["profile"].is_string());
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use owo_colors::OwoColorize as _;
+
+use crate::utils::print_message;
+
+/// Hold cargo package cache lock files using same advisory lock as cargo so
+/// cargo doesn't download or extract crates while cache is getting cleaned
+pub(crate) struct PackageCacheLock {
+    lock_files: Vec<PathBuf>,
+    wait: bool,
+    locked_files: Vec<File>,
+}
+
+impl PackageCacheLock {
+    /// create new package cache lock for provided lock files. Lock files are
+    /// acquired in provided order
+    pub(crate) fn new(lock_files: &[PathBuf], wait: bool) -> Self {
+        Self {
+            lock_files: lock_files.to_vec(),
+            wait,
+            locked_files: Vec::new(),
+        }
+    }
+
+    /// acquire exclusive lock on all lock files. If lock is held by other
+    /// process either wait for it to be released or return error according to
+    /// wait value
+    pub(crate) fn acquire(&mut self) -> Result<()> {
+        if !self.locked_files.is_empty() {
+            return Ok(());
+        }
+        for lock_file in &self.lock_files {
+            // nothing can be cleaned when cargo home doesn't exist
+            if !lock_file.parent().is_some_and(std::path::Path::exists) {
+                continue;
+            }
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(lock_file)
+                .with_context(|| format!("failed to open lock file {}", lock_file.display()))?;
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) if self.wait => {
+                    print_message(format_args!(
+                        "{} waiting for file lock on {}",
+                        "Blocking".yellow(),
+                        lock_file.display()
+                    ));
+                    file.lock().with_context(|| {
+                        format!("failed to acquire lock on {}", lock_file.display())
+                    })?;
+                }
+                Err(TryLockError::WouldBlock) => {
+                    self.locked_files.clear();
+                    anyhow::bail!(
+                        "package cache lock {} is held by another process such as running cargo \
+                         build. Try again after it completes or pass --wait to wait for lock",
+                        lock_file.display()
+                    );
+                }
+                Err(TryLockError::Error(err)) => {
+                    self.locked_files.clear();
+                    return Err(err).with_context(|| {
+                        format!("failed to acquire lock on {}", lock_file.display())
+                    });
+                }
+            }
+            self.locked_files.push(file);
+        }
+        Ok(())
+    }
+
+    /// release all acquired lock
+    pub(crate) fn release(&mut self) {
+        self.locked_files.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use tempfile::TempDir;
+
+    use super::PackageCacheLock;
+
+    #[test]
+    fn held_lock_is_refused_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let cargo_home = temp_dir.path();
+        let lock_files = [
+            cargo_home.join(".package-cache"),
+            cargo_home.join(".package-cache-mutate"),
+        ];
+        let held_file = File::create(&lock_files[1]).unwrap();
+        held_file.lock().unwrap();
+
+        let mut package_cache_lock = PackageCacheLock::new(&lock_files, false);
+        let error = package_cache_lock.acquire().unwrap_err().to_string();
+        assert!(error.contains(".package-cache-mutate"));
+        assert!(package_cache_lock.locked_files.is_empty());
+
+        held_file.unlock().unwrap();
+        package_cache_lock.acquire().unwrap();
+        assert_eq!(package_cache_lock.locked_files.len(), 2);
+        assert!(held_file.try_lock().is_err());
+
+        package_cache_lock.release();
+        assert!(held_file.try_lock().is_ok());
+    }
+
+    #[test]
+    fn missing_cargo_home_is_not_locked_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let cargo_home = temp_dir.path().join("missing");
+        let mut package_cache_lock =
+            PackageCacheLock::new(&[cargo_home.join(".package-cache")], false);
+        package_cache_lock.acquire().unwrap();
+        assert!(!cargo_home.exists());
+    }
+}
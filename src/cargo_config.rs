@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr as _;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::{Context as _, Result};
+use owo_colors::OwoColorize as _;
+use serde::Deserialize;
+use url::Url;
+
+use crate::utils::print_message;
+
+/// name of built in crates.io source used by cargo
+const CRATES_IO_SOURCE: &str = "crates-io";
+
+/// git index url of crates.io
+pub(crate) const CRATES_IO_INDEX: &str = "https://github.com/rust-lang/crates.io-index";
+
+/// sparse index url of crates.io
+pub(crate) const CRATES_IO_SPARSE_INDEX: &str = "sparse+https://index.crates.io/";
+
+/// maximum number of replace-with followed before source replacement is
+/// considered as cycle
+const MAX_REPLACEMENT_DEPTH: usize = 16;
+
+#[derive(Default, Deserialize)]
+struct CargoConfig {
+    #[serde(default)]
+    source: HashMap<String, SourceDefinition>,
+    #[serde(default)]
+    registries: HashMap<String, RegistryDefinition>,
+    #[serde(default)]
+    build: BuildDefinition,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BuildDefinition {
+    target_dir: Option<String>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct SourceDefinition {
+    replace_with: Option<String>,
+    registry: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RegistryDefinition {
+    index: Option<String>,
+}
+
+/// Source alias map built from `[source]` and `[registries]` table of cargo
+/// config files along with `build.target-dir` value
+#[derive(Clone, Default)]
+pub(crate) struct SourceConfig {
+    replace_with: HashMap<String, String>,
+    source_urls: HashMap<String, Url>,
+    target_dir: Option<PathBuf>,
+    dir_configs: DirConfigs,
+}
+
+/// Merged source config of each directory already looked up so config file of
+/// directory shared by many projects is read only once. Cache is not copied
+/// to cloned config
+#[derive(Default)]
+struct DirConfigs(Mutex<HashMap<PathBuf, Arc<SourceConfig>>>);
+
+impl Clone for DirConfigs {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl SourceConfig {
+    /// load source config from cargo config files of cargo home. Missing files
+    /// are skipped
+    pub(crate) fn load(config_files: &[PathBuf]) -> Result<Self> {
+        let mut source_config = Self::default();
+        source_config.source_urls.insert(
+            CRATES_IO_SOURCE.to_string(),
+            Url::from_str(CRATES_IO_INDEX).context("failed to parse crates.io index url")?,
+        );
+        for config_file in config_files {
+            source_config.merge_file(config_file)?;
+        }
+        Ok(source_config)
+    }
+
+    /// return source config for project directory. `.cargo/config.toml` of
+    /// project directory and its parent directories are merged so config closer
+    /// to project takes precedence same as cargo. Invalid config file is
+    /// skipped with warning so single broken project doesn't fail whole run
+    pub(crate) fn for_project(&self, project_dir: &Path) -> Arc<Self> {
+        let mut dir_configs = self
+            .dir_configs
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.dir_config(&mut dir_configs, project_dir)
+    }
+
+    /// return merged source config of directory reusing merged config of
+    /// already looked up directory
+    fn dir_config(&self, dir_configs: &mut HashMap<PathBuf, Arc<Self>>, dir: &Path) -> Arc<Self> {
+        if let Some(dir_config) = dir_configs.get(dir) {
+            return Arc::clone(dir_config);
+        }
+        let mut source_config = match dir.parent() {
+            Some(parent) => Self::clone(&self.dir_config(dir_configs, parent)),
+            None => self.clone(),
+        };
+        for file_name in ["config", "config.toml"] {
+            let config_path = dir.join(".cargo").join(file_name);
+            // merge into copy so partially merged invalid file is discarded
+            let mut merged = source_config.clone();
+            match merged.merge_file(&config_path) {
+                Ok(()) => source_config = merged,
+                Err(err) => {
+                    print_message(format_args!(
+                        "{} invalid cargo config {}: {}",
+                        "Skipped".yellow(),
+                        config_path.display(),
+                        err.root_cause()
+                    ));
+                }
+            }
+        }
+        let source_config = Arc::new(source_config);
+        dir_configs.insert(dir.to_path_buf(), Arc::clone(&source_config));
+        source_config
+    }
+
+    /// return target directory configured by `build.target-dir`. Relative
+    /// value is already resolved from directory containing `.cargo` directory
+    /// of config file same as cargo
+    pub(crate) fn target_dir(&self) -> Option<&Path> {
+        self.target_dir.as_deref()
+    }
+
+    /// return all registry urls known by config
+    pub(crate) fn registry_urls(&self) -> impl Iterator<Item = &Url> {
+        self.source_urls.values()
+    }
+
+    /// resolve registry url recorded in Cargo.lock to url of registry which is
+    /// actually used for downloading crates after following source
+    /// replacement. Url is returned as it is when it is not replaced
+    pub(crate) fn resolve(&self, url: &Url) -> Url {
+        // same url can be defined with multiple name so prefer replaced source
+        let Some(mut name) = self
+            .source_urls
+            .iter()
+            .filter(|(_, source_url)| canonical_registry(source_url) == canonical_registry(url))
+            .map(|(name, _)| name)
+            .max_by_key(|name| (self.replace_with.contains_key(*name), *name))
+        else {
+            return url.clone();
+        };
+        for _ in 0..MAX_REPLACEMENT_DEPTH {
+            match self.replace_with.get(name) {
+                Some(replacement) => name = replacement,
+                None => break,
+            }
+        }
+        self.source_urls
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| url.clone())
+    }
+
+    /// merge content of cargo config file. Values of merged file override
+    /// existing values
+    fn merge_file(&mut self, path: &Path) -> Result<()> {
+        if !path.is_file() {
+            return Ok(());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read cargo config {}", path.display()))?;
+        let cargo_config: CargoConfig = toml::from_str(&content)
+            .with_context(|| format!("failed to parse cargo config {}", path.display()))?;
+        for (name, source) in cargo_config.source {
+            if let Some(replace_with) = source.replace_with {
+                self.replace_with.insert(name.clone(), replace_with);
+            }
+            if let Some(registry) = source.registry {
+                self.source_urls
+                    .insert(name, parse_registry_url(&registry)?);
+            }
+        }
+        for (name, registry) in cargo_config.registries {
+            if let Some(index) = registry.index {
+                self.source_urls.insert(name, parse_registry_url(&index)?);
+            }
+        }
+        if let Some(target_dir) = cargo_config.build.target_dir {
+            let config_root = path.parent().and_then(Path::parent).unwrap_or(path);
+            self.target_dir = Some(config_root.join(target_dir));
+        }
+        Ok(())
+    }
+}
+
+/// parse registry url of cargo config. Sparse registry url keeps `sparse+`
+/// prefix same as in Cargo.lock source
+fn parse_registry_url(value: &str) -> Result<Url> {
+    Url::from_str(value).with_context(|| format!("failed to parse registry url {value}"))
+}
+
+/// return canonical identity of registry url. `registry+` and `sparse+`
+/// prefix, trailing slash and `.git` suffix are ignored and every spelling of
+/// crates.io index such as git and sparse index has same identity
+pub(crate) fn canonical_registry(url: &Url) -> String {
+    let value = url.as_str();
+    let value = value.strip_prefix("registry+").unwrap_or(value);
+    let value = value.strip_prefix("sparse+").unwrap_or(value);
+    let Ok(url) = Url::from_str(value) else {
+        return value.trim_end_matches('/').to_string();
+    };
+    let path = url.path().trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let host = url.host_str().unwrap_or_default();
+    if (host == "github.com" && path.eq_ignore_ascii_case("/rust-lang/crates.io-index"))
+        || (host == "index.crates.io" && path.is_empty())
+    {
+        return CRATES_IO_SOURCE.to_string();
+    }
+    let port = url
+        .port()
+        .map(|port| format!(":{port}"))
+        .unwrap_or_default();
+    format!("{}://{host}{port}{path}", url.scheme())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::str::FromStr as _;
+
+    use tempfile::TempDir;
+    use url::Url;
+
+    use super::{CRATES_IO_INDEX, SourceConfig, canonical_registry};
+
+    #[test]
+    fn source_replacement_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let cargo_home_config = root.join("config.toml");
+        fs::write(
+            &cargo_home_config,
+            r#"
+[source.crates-io]
+replace-with = "mirror"
+
+[source.mirror]
+registry = "sparse+https://mirror.example.com/index/"
+
+[registries.company]
+index = "https://git.example.com/index.git"
+"#,
+        )
+        .unwrap();
+        let source_config = SourceConfig::load(&[cargo_home_config]).unwrap();
+        let crates_io = Url::from_str(CRATES_IO_INDEX).unwrap();
+        assert_eq!(
+            source_config.resolve(&crates_io),
+            Url::from_str("sparse+https://mirror.example.com/index/").unwrap()
+        );
+        let company = Url::from_str("https://git.example.com/index.git").unwrap();
+        assert_eq!(source_config.resolve(&company), company);
+
+        // project config overrides cargo home config
+        let project = root.join("workspace/project");
+        fs::create_dir_all(root.join("workspace/.cargo")).unwrap();
+        fs::create_dir_all(&project).unwrap();
+        fs::write(
+            root.join("workspace/.cargo/config.toml"),
+            "[source.crates-io]\nreplace-with = \"company\"\n",
+        )
+        .unwrap();
+        let project_config = source_config.for_project(&project);
+        assert_eq!(project_config.resolve(&crates_io), company);
+
+        // invalid project config is skipped and merged config is cached
+        let broken = root.join("workspace/broken");
+        fs::create_dir_all(broken.join(".cargo")).unwrap();
+        fs::write(broken.join(".cargo/config.toml"), "[source.crates-io").unwrap();
+        let broken_config = source_config.for_project(&broken);
+        assert_eq!(broken_config.resolve(&crates_io), company);
+        fs::remove_dir_all(root.join("workspace/.cargo")).unwrap();
+        assert_eq!(
+            source_config.for_project(&project).resolve(&crates_io),
+            company
+        );
+    }
+
+    #[test]
+    fn replacement_cycle_is_stopped_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let cargo_home_config = root.join("config.toml");
+        fs::write(
+            &cargo_home_config,
+            r#"
+[source.crates-io]
+replace-with = "first"
+
+[source.first]
+replace-with = "crates-io"
+"#,
+        )
+        .unwrap();
+        let source_config = SourceConfig::load(&[cargo_home_config]).unwrap();
+        let crates_io = Url::from_str(CRATES_IO_INDEX).unwrap();
+        assert_eq!(source_config.resolve(&crates_io), crates_io);
+    }
+
+    #[test]
+    fn canonical_crates_io_test() {
+        for spelling in [
+            "https://github.com/rust-lang/crates.io-index",
+            "https://github.com/rust-lang/crates.io-index/",
+            "https://github.com/rust-lang/crates.io-index.git",
+            "registry+https://github.com/rust-lang/crates.io-index",
+            "sparse+https://index.crates.io/",
+            "sparse+https://index.crates.io",
+            "https://index.crates.io",
+        ] {
+            assert_eq!(
+                canonical_registry(&Url::from_str(spelling).unwrap()),
+                "crates-io",
+                "{spelling}"
+            );
+        }
+        assert_eq!(
+            canonical_registry(&Url::from_str("sparse+https://example.com/index/").unwrap()),
+            canonical_registry(&Url::from_str("https://example.com/index").unwrap())
+        );
+        assert_ne!(
+            canonical_registry(&Url::from_str("https://index.crates.io/mirror").unwrap()),
+            "crates-io"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use clap::Parser;
+use owo_colors::OwoColorize as _;
+
+use crate::bin_dir::{BinDir, InstalledPackage};
+use crate::dir_path::DirPath;
+use crate::utils::{parse_duration, print_message};
+
+#[derive(Debug, Parser)]
+#[command(
+    about = "Perform operation to binaries installed using cargo install",
+    arg_required_else_help = true
+)]
+#[expect(clippy::struct_excessive_bools)]
+pub(crate) struct Bin {
+    #[arg(
+        long = "dry-run",
+        short = 'n',
+        help = "Run command in dry run mode to see what would be done"
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "list",
+        short = 'l',
+        help = "List installed binaries with package, version, source, features and profile which \
+                installed them"
+    )]
+    list: bool,
+    #[arg(
+        long = "prune-stale",
+        help = "Remove stale install metadata of binaries which no longer exist"
+    )]
+    prune_stale: bool,
+    #[arg(
+        long = "stale",
+        short = 's',
+        help = "List binaries whose install metadata is missing or stale"
+    )]
+    stale: bool,
+    #[arg(
+        long = "uninstall",
+        short = 'u',
+        help = "Uninstall package same as cargo uninstall. Package can be name or name@version",
+        value_name = "package"
+    )]
+    uninstall: Vec<String>,
+    #[arg(
+        long = "uninstall-unused",
+        help = "Uninstall packages whose binaries are not used for unused-for duration",
+        requires = "unused_for"
+    )]
+    uninstall_unused: bool,
+    #[arg(
+        long = "unused-for",
+        help = "Only operate on packages whose binaries are not used for provided duration such \
+                as 30d or 4w",
+        value_name = "duration",
+        value_parser = parse_duration
+    )]
+    unused_for: Option<Duration>,
+}
+
+impl Bin {
+    /// check if command modifies cargo install metadata or binaries
+    pub(super) fn is_destructive(&self) -> bool {
+        !self.dry_run && (self.prune_stale || self.uninstall_unused || !self.uninstall.is_empty())
+    }
+
+    pub(super) fn run(&self, dir_path: &DirPath) -> Result<()> {
+        let dry_run = self.dry_run;
+        let mut bin_dir = BinDir::load(
+            dir_path.bin_dir(),
+            dir_path.crates_toml_file(),
+            dir_path.crates2_json_file(),
+        )?;
+        let now = SystemTime::now();
+        let bin_path = dir_path.bin_dir();
+        let is_unused = |package: &InstalledPackage| {
+            self.unused_for.is_none_or(|duration| {
+                package.bins().iter().all(|bin| {
+                    // binary is never read by cargo trim so its access time
+                    // records when it was last run
+                    let accessed = bin_path
+                        .join(bin)
+                        .metadata()
+                        .and_then(|metadata| metadata.accessed())
+                        .ok();
+                    accessed.is_none_or(|time| {
+                        now.duration_since(time)
+                            .is_ok_and(|elapsed| elapsed >= duration)
+                    })
+                })
+            })
+        };
+        let packages = bin_dir
+            .packages()
+            .into_iter()
+            .filter(is_unused)
+            .collect::<Vec<_>>();
+
+        if self.list {
+            list_packages(&packages);
+        }
+        if self.stale {
+            list_stale(&bin_dir, &packages)?;
+        }
+        if self.prune_stale {
+            let mut total_pruned = 0;
+            for package in packages.iter().filter(|package| package.is_stale()) {
+                bin_dir.prune_stale(package, dry_run)?;
+                total_pruned += 1;
+            }
+            print_message(format_args!(
+                "{}",
+                format!("Pruned stale install metadata of {total_pruned} packages").blue()
+            ));
+        }
+        self.uninstall_packages(&mut bin_dir, &packages)
+    }
+
+    // uninstall packages provided by uninstall or all packages matching unused for
+    // duration when uninstall unused is used
+    fn uninstall_packages(
+        &self,
+        bin_dir: &mut BinDir,
+        packages: &[InstalledPackage],
+    ) -> Result<()> {
+        if self.uninstall.is_empty() && !self.uninstall_unused {
+            return Ok(());
+        }
+        for spec in &self.uninstall {
+            let matched = packages.iter().filter(|package| package.matches(spec));
+            if matched.clone().count() > 1 && !spec.contains('@') {
+                anyhow::bail!(
+                    "multiple installed packages match {spec}. Use name@version to specify package"
+                );
+            }
+            if matched.clone().count() == 0 {
+                print_message(format_args!("No installed package matches {spec}"));
+            }
+        }
+        let mut total_uninstalled = 0;
+        for package in packages {
+            if self.uninstall_unused || self.uninstall.iter().any(|spec| package.matches(spec)) {
+                bin_dir.uninstall(package, self.dry_run)?;
+                print_message(format_args!(
+                    "{} {} v{}",
+                    "Uninstalled".red(),
+                    package.name(),
+                    package.version()
+                ));
+                total_uninstalled += 1;
+            }
+        }
+        print_message(format_args!(
+            "{}",
+            format!("{total_uninstalled} packages uninstalled").blue()
+        ));
+        Ok(())
+    }
+}
+
+// list installed packages along with binaries installed by them
+fn list_packages(packages: &[InstalledPackage]) {
+    for package in packages {
+        let features = package
+            .features()
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(",");
+        print_message(format_args!(
+            "{} v{} ({}) features: [{features}] profile: {}",
+            package.name(),
+            package.version(),
+            package.source(),
+            package.profile().unwrap_or("unknown")
+        ));
+        for bin in package.bins() {
+            let status = if package.missing_bins().contains(bin) {
+                " (missing)"
+            } else {
+                ""
+            };
+            print_message(format_args!("   \u{2514} {bin}{status}"));
+        }
+    }
+    print_message(format_args!(
+        "{}",
+        format!("Total no of installed packages: {}", packages.len()).blue()
+    ));
+}
+
+// list packages with stale metadata and binaries without metadata
+fn list_stale(bin_dir: &BinDir, packages: &[InstalledPackage]) -> Result<()> {
+    for package in packages.iter().filter(|package| package.is_stale()) {
+        if package.missing_bins().is_empty() {
+            print_message(format_args!(
+                "{} v{} is only recorded in one of .crates.toml and .crates2.json",
+                package.name(),
+                package.version()
+            ));
+        }
+        for bin in package.missing_bins() {
+            print_message(format_args!(
+                "{bin} of {} v{} is recorded but missing from bin directory",
+                package.name(),
+                package.version()
+            ));
+        }
+    }
+    for bin in bin_dir.untracked_bins()? {
+        print_message(format_args!("{bin} has no install metadata"));
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use clap::Parser;
+use owo_colors::OwoColorize as _;
+
+use crate::dir_path::DirPath;
+use crate::scan_cache::clear_scan_cache;
+use crate::utils::print_message;
+
+#[derive(Debug, Parser)]
+#[command(
+    about = "Manage scan cache which stores parsed Cargo.lock files, directory listings and crate \
+             sizes",
+    arg_required_else_help = true
+)]
+pub(crate) struct Cache {
+    #[command(subcommand)]
+    sub: CacheSubCommand,
+}
+
+#[derive(Debug, Parser)]
+enum CacheSubCommand {
+    Clear(Clear),
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Remove scan cache so next run scans everything again")]
+struct Clear {
+    #[arg(
+        long = "dry-run",
+        short = 'n',
+        help = "Run command in dry run mode to see what would be done"
+    )]
+    dry_run: bool,
+}
+
+impl Cache {
+    pub(super) fn run(&self, dir_path: &DirPath) -> Result<()> {
+        match &self.sub {
+            CacheSubCommand::Clear(clear) => {
+                let scan_cache_file = dir_path.scan_cache_file();
+                if clear_scan_cache(scan_cache_file, clear.dry_run)? {
+                    let message = format!("Cleared scan cache {}", scan_cache_file.display());
+                    if clear.dry_run {
+                        print_message(format_args!("{} {message}", "Dry run:".yellow()));
+                    } else {
+                        print_message(format_args!("{message}"));
+                    }
+                } else {
+                    print_message(format_args!("Scan cache is already empty"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use clap::Parser;
+
+use super::utils::Report;
+use crate::dedup::dedup_files;
+use crate::dir_path::DirPath;
+
+#[derive(Debug, Parser)]
+#[command(about = "Replace identical files of registry src with hard links")]
+pub(crate) struct Dedup {
+    #[arg(
+        long = "dry-run",
+        short = 'n',
+        help = "Run command in dry run mode to see what would be done"
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "include-checkouts",
+        help = "Also link files of git checkouts. Checkout is working tree which can be modified \
+                in place so editing linked file changes every copy of it"
+    )]
+    include_checkouts: bool,
+}
+
+impl Dedup {
+    /// check if command modifies cargo cache
+    pub(super) fn is_destructive(&self) -> bool {
+        !self.dry_run
+    }
+
+    pub(super) fn run(&self, dir_path: &DirPath, report: &mut Report<'_>) -> Result<()> {
+        let mut roots = vec![dir_path.src_dir().as_path()];
+        if self.include_checkouts {
+            roots.push(dir_path.checkout_dir());
+        }
+        let summary = dedup_files(&roots, self.dry_run)?;
+        report.dedup(self.dry_run, summary.linked, summary.reclaimed);
+        Ok(())
+    }
+}
@@ -1,17 +1,15 @@
-use std::io::Write as _;
 use std::path::Path;
+use std::time::Duration;
 
-use anyhow::{Context as _, Result};
+use anyhow::Result;
 use clap::Parser;
-use owo_colors::OwoColorize as _;
 
-use super::utils::{print_dash, show_top_number_crates};
-use super::{query_full_width, query_print};
+use super::TopOrder;
+use super::utils::{Report, confirm, show_top_number_crates};
 use crate::crate_detail::{CrateDetail, CrateMetaData};
 use crate::dir_path::DirPath;
 use crate::git_dir::GitDir;
-use crate::list_crate::CrateList;
-use crate::utils::{convert_pretty, get_size};
+use crate::utils::{parse_duration, print_message};
 #[derive(Debug, Parser)]
 #[command(
     about = "Perform operation only to git related cache file",
@@ -63,55 +61,79 @@ pub(crate) struct Git {
         value_name = "number"
     )]
     top: Option<usize>,
+    #[arg(
+        long = "top-by",
+        value_enum,
+        default_value_t = TopOrder::Size,
+        help = "Order used by top. Last used order shows least recently used crates first",
+        value_name = "order",
+        requires = "top"
+    )]
+    top_by: TopOrder,
+    #[arg(
+        long = "unused-for",
+        help = "Clean git crates which are not used for provided duration such as 90d or 6w. \
+                Without cargo global cache tracker last used time is download time of crate",
+        value_name = "duration",
+        value_parser = parse_duration
+    )]
+    unused_for: Option<Duration>,
 }
 
 impl Git {
-    #[expect(clippy::too_many_lines)]
+    /// check if command removes any file from cache
+    pub(super) fn is_destructive(&self) -> bool {
+        !self.dry_run
+            && (self.all
+                || self.light_cleanup
+                || self.old
+                || self.old_orphan
+                || self.orphan
+                || self.unused_for.is_some())
+    }
+
+    /// check if command selects crates by last used time
+    pub(super) fn uses_last_used(&self) -> bool {
+        self.unused_for.is_some()
+    }
+
     pub(super) fn run(
         &self,
         dir_path: &DirPath,
-        crate_list: &CrateList,
-        crate_detail: &CrateDetail,
+        report: &mut Report<'_>,
         directory_is_empty: bool,
     ) -> Result<()> {
         let dry_run = self.dry_run;
+        let crate_list = report.crate_list();
+        let crate_detail = report.crate_detail();
 
         if self.light_cleanup {
             let light_cleanup_success = light_cleanup_git(dir_path.checkout_dir(), dry_run);
             if !light_cleanup_success {
-                println!("Failed to delete some folder during light cleanup");
+                print_message(format_args!(
+                    "Failed to delete some folder during light cleanup"
+                ));
             }
         }
 
         if let Some(number) = self.top {
-            let max_width = std::cmp::max(
-                crate_detail
-                    .source_infos()
-                    .keys()
-                    .map(String::len)
-                    .max()
-                    .unwrap_or(9),
-                9,
-            ) + 2;
-            top_crates_git(crate_detail, max_width, number);
+            top_crates_git(report, number, self.top_by);
         }
 
         if self.query {
-            let final_size = query_size_git(dir_path, crate_list, crate_detail);
-            query_print("Total size", &convert_pretty(final_size));
+            let final_size = query_size_git(dir_path, report);
+            report.query("Total size", "total", None, final_size);
         }
 
         if self.old {
-            let (sized_cleaned, total_crate_removed) =
-                clean_git(crate_list.old_git(), crate_detail, dry_run)?;
-            println!(
-                "{}",
-                format!(
-                    "{total_crate_removed} old crates removed which had occupied {}",
-                    convert_pretty(sized_cleaned)
-                )
-                .blue()
-            );
+            let removed_git = clean_git(
+                &crate_list.unprotected(crate_list.old_git()),
+                crate_detail,
+                dry_run,
+            )?;
+            report.removed("old", dry_run, &[], &removed_git, |count, size| {
+                format!("{count} old crates removed which had occupied {size}")
+            });
         }
 
         if self.old_orphan {
@@ -122,33 +144,21 @@ impl Git {
                                     init' to initialize current directory as rust project \
                                     directory or pass cargo trim set -d <directory> for setting \
                                     rust project directory";
-                println!("{}", warning_text.yellow());
-                let mut input = String::new();
-                print!("Do you want to continue? (y/N) ");
-                std::io::stdout()
-                    .flush()
-                    .context("failed to flush output stream")?;
-                std::io::stdin()
-                    .read_line(&mut input)
-                    .context("error: unable to read user input")?;
-                let trimmed_input = input.trim().to_ascii_lowercase();
-                // if answer is any instead of yes and y return
-                if !["y", "yes"].contains(&trimmed_input.as_str()) {
+                if !confirm(warning_text)? {
                     return Ok(());
                 }
             }
-            let (sized_cleaned, total_crate_removed) =
-                clean_git(&crate_list.old_orphan_git(), crate_detail, dry_run)?;
-
-            println!(
-                "{}",
+            let removed_git = clean_git(
+                &crate_list.unprotected(&crate_list.old_orphan_git()),
+                crate_detail,
+                dry_run,
+            )?;
+            report.removed("old_orphan", dry_run, &[], &removed_git, |count, size| {
                 format!(
-                    "{total_crate_removed} crates which are both old and orphan crate removed \
-                     which had occupied {}",
-                    convert_pretty(sized_cleaned)
+                    "{count} crates which are both old and orphan crate removed which had \
+                     occupied {size}"
                 )
-                .blue()
-            );
+            });
         }
 
         if self.orphan {
@@ -159,45 +169,40 @@ impl Git {
                                     trim init' to initialize current directory as rust project \
                                     directory or pass cargo trim set -d <directory> for setting \
                                     rust project directory";
-                println!("{}", warning_text.yellow());
-                let mut input = String::new();
-                print!("Do you want to continue? (y/N) ");
-                std::io::stdout()
-                    .flush()
-                    .context("failed to flush output stream")?;
-                std::io::stdin()
-                    .read_line(&mut input)
-                    .context("error: unable to read user input")?;
-                let trimmed_input = input.trim().to_ascii_lowercase();
-                // If answer is not y or yes then return
-                if !["y", "yes"].contains(&trimmed_input.as_str()) {
+                if !confirm(warning_text)? {
                     return Ok(());
                 }
             }
-            let (sized_cleaned, total_crate_removed) =
-                clean_git(crate_list.orphan_git(), crate_detail, dry_run)?;
+            let removed_git = clean_git(
+                &crate_list.unprotected(crate_list.orphan_git()),
+                crate_detail,
+                dry_run,
+            )?;
+            report.removed("orphan", dry_run, &[], &removed_git, |count, size| {
+                format!("{count} orphan crates removed which had occupied {size}")
+            });
+        }
 
-            println!(
-                "{}",
-                format!(
-                    "{total_crate_removed} orphan crates removed which had occupied {}",
-                    convert_pretty(sized_cleaned)
-                )
-                .blue()
-            );
+        if let Some(duration) = self.unused_for {
+            let removed_git = clean_git(
+                &crate_list.unprotected(&crate_list.unused_git(duration)),
+                crate_detail,
+                dry_run,
+            )?;
+            report.removed("unused", dry_run, &[], &removed_git, |count, size| {
+                format!("{count} unused crates removed which had occupied {size}")
+            });
         }
 
         if self.all {
-            let (sized_cleaned, total_crate_removed) =
-                clean_git(crate_list.installed_git(), crate_detail, dry_run)?;
-            println!(
-                "{}",
-                format!(
-                    "Total size of {total_crate_removed} crates removed :- {}",
-                    convert_pretty(sized_cleaned)
-                )
-                .blue()
-            );
+            let removed_git = clean_git(
+                &crate_list.unprotected(crate_list.installed_git()),
+                crate_detail,
+                dry_run,
+            )?;
+            report.removed("all", dry_run, &[], &removed_git, |count, size| {
+                format!("Total size of {count} crates removed :- {size}")
+            });
         }
 
         Ok(())
@@ -211,57 +216,65 @@ pub(super) fn light_cleanup_git(checkout_dir: &Path, dry_run: bool) -> bool {
 }
 
 // Show top git crates
-pub(super) fn top_crates_git(crate_detail: &CrateDetail, first_width: usize, number: usize) {
+pub(super) fn top_crates_git(report: &mut Report<'_>, number: usize, order: TopOrder) {
+    let crate_detail = report.crate_detail();
     show_top_number_crates(
+        report,
         crate_detail.git_crates_archive(),
         "git_archive",
-        first_width,
         number,
+        order,
     );
     show_top_number_crates(
+        report,
         crate_detail.git_crates_source(),
         "git_source",
-        first_width,
         number,
+        order,
     );
 }
 
-pub(super) fn query_size_git(
-    dir_path: &DirPath,
-    crate_list: &CrateList,
-    crate_detail: &CrateDetail,
-) -> u64 {
-    let git_dir_size = get_size(dir_path.git_dir()).unwrap_or(0_u64);
-    query_print(
-        &format!(
-            "Total size of {} .cargo/git crates:",
-            crate_list.installed_git().len()
-        ),
-        &convert_pretty(git_dir_size),
+pub(super) fn query_size_git(dir_path: &DirPath, report: &mut Report<'_>) -> u64 {
+    let git_dir_size = report
+        .crate_detail()
+        .dir_size(dir_path.git_dir())
+        .unwrap_or(0_u64);
+    let git_count = report.crate_list().installed_git().len();
+    let checkout_count = report.crate_detail().git_crates_archive().len();
+    let db_count = report.crate_detail().git_crates_source().len();
+    report.query(
+        &format!("Total size of {git_count} .cargo/git crates:"),
+        "git",
+        Some(git_count),
+        git_dir_size,
     );
-    query_print(
-        &format!(
-            "   \u{251c} Size of {} .cargo/git/checkout folder",
-            crate_detail.git_crates_archive().len()
-        ),
-        &convert_pretty(get_size(dir_path.checkout_dir()).unwrap_or(0_u64)),
+    report.query(
+        &format!("   \u{251c} Size of {checkout_count} .cargo/git/checkout folder"),
+        "git_checkout",
+        Some(checkout_count),
+        report
+            .crate_detail()
+            .dir_size(dir_path.checkout_dir())
+            .unwrap_or(0_u64),
     );
-    query_print(
-        &format!(
-            "   \u{2514} Size of {} .cargo/git/db folder",
-            crate_detail.git_crates_source().len()
-        ),
-        &convert_pretty(get_size(dir_path.db_dir()).unwrap_or(0_u64)),
+    report.query(
+        &format!("   \u{2514} Size of {db_count} .cargo/git/db folder"),
+        "git_db",
+        Some(db_count),
+        report
+            .crate_detail()
+            .dir_size(dir_path.db_dir())
+            .unwrap_or(0_u64),
     );
-    print_dash(query_full_width());
+    report.query_separator();
     git_dir_size
 }
 
-// perform clean on git crates
+// perform clean on git crates and return removed crates
 pub(super) fn clean_git(
     crate_metadata_list: &[CrateMetaData],
     crate_detail: &CrateDetail,
     dry_run: bool,
-) -> Result<(u64, usize)> {
+) -> Result<Vec<CrateMetaData>> {
     GitDir::remove_crate_list(crate_detail, crate_metadata_list, dry_run)
 }
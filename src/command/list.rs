@@ -1,8 +1,12 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
 use clap::Parser;
 use owo_colors::OwoColorize as _;
 
-use super::utils::crate_list_type;
-use crate::list_crate::CrateList;
+use super::utils::Report;
+use crate::utils::{parse_duration, print_message};
 
 #[derive(Debug, Parser)]
 #[command(about = "List crates", arg_required_else_help = true)]
@@ -23,150 +27,142 @@ pub(crate) struct List {
     #[arg(
         long = "project",
         short = 'p',
-        help = "List all detected Rust projects (directories containing a scanned Cargo.lock)"
+        help = "List all detected Rust projects grouped by workspace with their dependencies"
     )]
     project: bool,
+    #[arg(
+        long = "unused-for",
+        help = "List crates which are not used for provided duration such as 90d or 6w. Without \
+                cargo global cache tracker last used time is download time of crate",
+        value_name = "duration",
+        value_parser = parse_duration
+    )]
+    unused_for: Option<Duration>,
+    #[arg(
+        long = "used-by",
+        help = "List cached crates used by project of provided directory",
+        value_name = "project-dir"
+    )]
+    used_by: Option<PathBuf>,
+    #[arg(
+        long = "exclusive-to",
+        help = "List cached crates which are only used by project of provided directory",
+        value_name = "project-dir"
+    )]
+    exclusive_to: Option<PathBuf>,
 }
 
 impl List {
-    pub(super) fn run(
-        &self,
-        crate_list: &CrateList,
-        source_url_max_width: usize,
-        directory_is_empty: bool,
-    ) {
+    /// check if command selects crates by last used time
+    pub(super) fn uses_last_used(&self) -> bool {
+        self.unused_for.is_some()
+    }
+
+    pub(super) fn run(&self, report: &mut Report<'_>, directory_is_empty: bool) -> Result<()> {
         if self.all {
-            list_all(crate_list, source_url_max_width);
+            list_all(report);
         }
         if self.old {
-            list_old(crate_list, source_url_max_width);
+            list_old(report);
         }
         if self.old_orphan {
-            list_old_orphan(crate_list, source_url_max_width, directory_is_empty);
+            list_old_orphan(report, directory_is_empty);
         }
         if self.orphan {
-            list_orphan(crate_list, source_url_max_width, directory_is_empty);
+            list_orphan(report, directory_is_empty);
         }
         if self.project {
-            list_projects(crate_list);
+            list_projects(report);
+        }
+        if let Some(duration) = self.unused_for {
+            list_unused(report, duration);
+        }
+        if let Some(project_dir) = &self.used_by {
+            list_used_by(report, project_dir)?;
+        }
+        if let Some(project_dir) = &self.exclusive_to {
+            list_exclusive_to(report, project_dir)?;
         }
+        Ok(())
     }
 }
 
-fn list_projects(crate_list: &CrateList) {
-    let lock_files = crate_list.cargo_lock_files().paths();
-    println!(
-        "{}",
-        format!("Total detected projects: {}", lock_files.len()).blue()
-    );
-    for (index, lock_file) in lock_files.iter().enumerate() {
-        // A project is the directory that contains the detected Cargo.lock.
-        let project_dir = lock_file.parent().unwrap_or(lock_file);
-        println!(
-            "{}: {}",
-            format!("Project [{index}]").blue(),
-            project_dir.display()
-        );
-    }
+fn list_projects(report: &mut Report<'_>) {
+    let crate_list = report.crate_list();
+    report.projects(crate_list.projects());
 }
 
-fn list_all(crate_list: &CrateList, first_width: usize) {
-    let second_width = std::cmp::max(
-        crate_list
-            .installed_bin()
-            .iter()
-            .chain(crate_list.installed_registry())
-            .chain(crate_list.installed_git())
-            .map(|cm| {
-                if let Some(version) = cm.version() {
-                    cm.name().len() + version.to_string().len() + 1
-                } else {
-                    cm.name().len()
-                }
-            })
-            .max()
-            .unwrap_or(30),
-        30,
-    ) + 2;
-    crate_list_type(
-        crate_list.installed_bin(),
-        first_width,
-        second_width,
-        "INSTALLED BIN",
-    );
-    crate_list_type(
-        crate_list.installed_registry(),
-        first_width,
-        second_width,
-        "REGISTRY INSTALLED CRATE",
-    );
-    crate_list_type(
-        crate_list.installed_git(),
-        first_width,
-        second_width,
-        "GIT INSTALLED CRATE",
+fn list_used_by(report: &mut Report<'_>, project_dir: &Path) -> Result<()> {
+    let crate_list = report.crate_list();
+    let project = crate_list
+        .find_project(project_dir)
+        .with_context(|| format!("no project detected at {}", project_dir.display()))?;
+    let (registry, git) = crate_list.used_by(project);
+    report.crates(
+        "used_by",
+        &[
+            ("registry", "REGISTRY CRATE USED BY PROJECT", &registry),
+            ("git", "GIT CRATE USED BY PROJECT", &git),
+        ],
     );
+    Ok(())
 }
 
-fn list_old(crate_list: &CrateList, first_width: usize) {
-    let second_width = std::cmp::max(
-        crate_list
-            .old_registry()
-            .iter()
-            .chain(crate_list.old_git())
-            .map(|cm| {
-                if let Some(version) = cm.version() {
-                    cm.name().len() + version.to_string().len() + 1
-                } else {
-                    cm.name().len()
-                }
-            })
-            .max()
-            .unwrap_or(30),
-        30,
-    ) + 2;
-    crate_list_type(
-        crate_list.old_registry(),
-        first_width,
-        second_width,
-        "REGISTRY OLD CRATE",
+fn list_exclusive_to(report: &mut Report<'_>, project_dir: &Path) -> Result<()> {
+    let crate_list = report.crate_list();
+    let project = crate_list
+        .find_project(project_dir)
+        .with_context(|| format!("no project detected at {}", project_dir.display()))?;
+    let (registry, git) = crate_list.exclusive_to(project);
+    report.crates(
+        "exclusive_to",
+        &[
+            ("registry", "REGISTRY CRATE EXCLUSIVE TO PROJECT", &registry),
+            ("git", "GIT CRATE EXCLUSIVE TO PROJECT", &git),
+        ],
     );
-    crate_list_type(
-        crate_list.old_git(),
-        first_width,
-        second_width,
-        "GIT OLD CRATE",
+    Ok(())
+}
+
+fn list_all(report: &mut Report<'_>) {
+    let crate_list = report.crate_list();
+    report.crates(
+        "all",
+        &[
+            ("bin", "INSTALLED BIN", crate_list.installed_bin()),
+            (
+                "registry",
+                "REGISTRY INSTALLED CRATE",
+                crate_list.installed_registry(),
+            ),
+            ("git", "GIT INSTALLED CRATE", crate_list.installed_git()),
+        ],
     );
 }
 
-fn list_old_orphan(crate_list: &CrateList, first_width: usize, directory_is_empty: bool) {
-    let second_width = std::cmp::max(
-        crate_list
-            .old_orphan_registry()
-            .iter()
-            .chain(&crate_list.old_orphan_git())
-            .map(|cm| {
-                if let Some(version) = cm.version() {
-                    cm.name().len() + version.to_string().len() + 1
-                } else {
-                    cm.name().len()
-                }
-            })
-            .max()
-            .unwrap_or(30),
-        30,
-    ) + 2;
-    crate_list_type(
-        &crate_list.old_orphan_registry(),
-        first_width,
-        second_width,
-        "REGISTRY OLD+ORPHAN CRATE",
+fn list_old(report: &mut Report<'_>) {
+    let crate_list = report.crate_list();
+    report.crates(
+        "old",
+        &[
+            ("registry", "REGISTRY OLD CRATE", crate_list.old_registry()),
+            ("git", "GIT OLD CRATE", crate_list.old_git()),
+        ],
     );
-    crate_list_type(
-        &crate_list.old_orphan_git(),
-        first_width,
-        second_width,
-        "GIT OLD+ORPHAN CRATE",
+}
+
+fn list_old_orphan(report: &mut Report<'_>, directory_is_empty: bool) {
+    let crate_list = report.crate_list();
+    report.crates(
+        "old_orphan",
+        &[
+            (
+                "registry",
+                "REGISTRY OLD+ORPHAN CRATE",
+                &crate_list.old_orphan_registry(),
+            ),
+            ("git", "GIT OLD+ORPHAN CRATE", &crate_list.old_orphan_git()),
+        ],
     );
     // print warning if no directory present in config file
     if directory_is_empty {
@@ -175,38 +171,22 @@ fn list_old_orphan(crate_list: &CrateList, first_width: usize, directory_is_empt
                             they are not orphan crates. Run command 'cargo trim init' to \
                             initialize current directory as rust project directory or pass cargo \
                             trim set -d <directory> for setting rust project directory";
-        println!("{}", warning_text.yellow());
+        print_message(format_args!("{}", warning_text.yellow()));
     }
 }
 
-fn list_orphan(crate_list: &CrateList, first_width: usize, directory_is_empty: bool) {
-    let second_width = std::cmp::max(
-        crate_list
-            .orphan_registry()
-            .iter()
-            .chain(crate_list.orphan_git())
-            .map(|cm| {
-                if let Some(version) = cm.version() {
-                    cm.name().len() + version.to_string().len() + 1
-                } else {
-                    cm.name().len()
-                }
-            })
-            .max()
-            .unwrap_or(30),
-        30,
-    ) + 2;
-    crate_list_type(
-        crate_list.orphan_registry(),
-        first_width,
-        second_width,
-        "REGISTRY ORPHAN CRATE",
-    );
-    crate_list_type(
-        crate_list.orphan_git(),
-        first_width,
-        second_width,
-        "GIT ORPHAN CRATE",
+fn list_orphan(report: &mut Report<'_>, directory_is_empty: bool) {
+    let crate_list = report.crate_list();
+    report.crates(
+        "orphan",
+        &[
+            (
+                "registry",
+                "REGISTRY ORPHAN CRATE",
+                crate_list.orphan_registry(),
+            ),
+            ("git", "GIT ORPHAN CRATE", crate_list.orphan_git()),
+        ],
     );
     // print warning if directory config is empty
     if directory_is_empty {
@@ -215,6 +195,21 @@ fn list_orphan(crate_list: &CrateList, first_width: usize, directory_is_empty: b
                             'cargo trim init' to initialize current directory as rust project \
                             directory or pass cargo trim set -d <directory> for setting rust \
                             project directory";
-        println!("{}", warning_text.yellow());
+        print_message(format_args!("{}", warning_text.yellow()));
     }
 }
+
+fn list_unused(report: &mut Report<'_>, duration: Duration) {
+    let crate_list = report.crate_list();
+    report.crates(
+        "unused",
+        &[
+            (
+                "registry",
+                "REGISTRY UNUSED CRATE",
+                &crate_list.unused_registry(duration),
+            ),
+            ("git", "GIT UNUSED CRATE", &crate_list.unused_git(duration)),
+        ],
+    );
+}
@@ -1,31 +1,49 @@
 use std::collections::HashSet;
 use std::fs;
-use std::io::Write as _;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{Context as _, Result};
 use clap::{Parser, ValueEnum};
 use owo_colors::OwoColorize as _;
 
-use self::utils::{print_dash, query_full_width, query_print, show_top_number_crates};
+use self::utils::{Report, confirm, show_top_number_crates};
+use crate::cache_lock::PackageCacheLock;
+use crate::cargo_config::SourceConfig;
 use crate::command::git::clean_git;
 use crate::command::registry::clean_registry;
 use crate::config_file::ConfigFile;
-use crate::crate_detail::CrateDetail;
+use crate::crate_detail::{CrateDetail, CrateMetaData};
 use crate::dir_path::DirPath;
+use crate::dir_size::set_disk_size_mode;
 use crate::list_crate::CrateList;
+use crate::missing_lock::resolve_missing_locks;
 use crate::registry_dir::RegistryDir;
-use crate::utils::{convert_pretty, delete_folder, get_inode_handled_size};
+use crate::scan_cache::ScanCache;
+use crate::trash::enable_trash;
+use crate::utils::{
+    convert_pretty, delete_folder, get_inode_handled_size, parse_duration, parse_size,
+    print_message, set_machine_readable_output,
+};
 
+mod bin;
+mod cache;
 mod clear;
 mod config;
+mod dedup;
 mod git;
 mod init;
 mod list;
 mod registry;
+mod remove;
+mod restore;
 mod set;
+mod target;
+mod trash;
 mod unset;
 mod utils;
+mod verify;
+mod why;
 
 #[derive(Debug, Parser)]
 enum SubCommand {
@@ -37,6 +55,15 @@ enum SubCommand {
     List(list::List),
     Git(git::Git),
     Registry(registry::Registry),
+    Remove(remove::Remove),
+    Restore(restore::Restore),
+    Trash(trash::Trash),
+    Target(target::Target),
+    Bin(bin::Bin),
+    Why(why::Why),
+    Cache(cache::Cache),
+    Dedup(dedup::Dedup),
+    Verify(verify::Verify),
 }
 
 #[derive(Debug, Parser)]
@@ -66,6 +93,16 @@ pub(crate) struct Command {
         help = "Run command in dry run mode to see what would be done"
     )]
     dry_run: bool,
+    #[arg(
+        long = "format",
+        value_enum,
+        default_value_t = OutputFormat::Table,
+        global = true,
+        help = "Output format of list, query, top, clean, dedup and verify reports. Messages are \
+                written to stderr for json and ndjson format",
+        value_name = "format"
+    )]
+    format: OutputFormat,
     #[arg(
         long = "gc",
         short = 'g',
@@ -81,6 +118,15 @@ pub(crate) struct Command {
         env = "TRIM_IGNORE"
     )]
     ignore: Option<Vec<String>>,
+    #[arg(
+        long = "keep",
+        help = "Number of newest versions of each crate which are not considered old for \
+                current command",
+        value_name = "number",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        env = "TRIM_KEEP"
+    )]
+    keep: Option<usize>,
     #[arg(
         long = "light",
         short = 'l',
@@ -88,6 +134,21 @@ pub(crate) struct Command {
                 internet"
     )]
     light_cleanup: bool,
+    #[arg(
+        long = "max-size",
+        help = "Clean least recently used crates until size of registry & git cache is under \
+                provided size such as 10GB. Orphan crates are removed before used crates. \
+                Without cargo global cache tracker last used time is download time of crate",
+        value_name = "size",
+        value_parser = parse_size
+    )]
+    max_size: Option<u64>,
+    #[arg(
+        long = "no-cache",
+        help = "Scan everything again without reading or updating scan cache for current command",
+        env = "TRIM_NO_CACHE"
+    )]
+    no_cache: bool,
     #[arg(
         long,
         help = "Do not scan hidden folder for current command. Takes precedence over \
@@ -102,6 +163,13 @@ pub(crate) struct Command {
         env = "TRIM_NOT_SCAN_TARGET_FOLDER"
     )]
     no_scan_target_folder: bool,
+    #[arg(
+        long = "no-wait",
+        global = true,
+        help = "Return error instead of waiting when cargo package cache lock is held by other \
+                process. Takes precedence over wait"
+    )]
+    no_wait: bool,
     #[arg(long = "old", short = 'o', help = "Clean old cache crates")]
     old: bool,
     #[arg(
@@ -123,6 +191,14 @@ pub(crate) struct Command {
         help = "Return size of different .cargo/cache folders"
     )]
     query: bool,
+    #[arg(
+        long = "resolve-missing-locks",
+        help = "Generate temporary Cargo.lock with cargo generate-lockfile --offline for \
+                workspaces which don't have Cargo.lock so their dependencies are not orphan for \
+                current command. Project directory is never modified",
+        env = "TRIM_RESOLVE_MISSING_LOCKS"
+    )]
+    resolve_missing_locks: bool,
     #[arg(
         long = "scan-hidden-folder",
         help = "Scan hidden folder for current command",
@@ -135,18 +211,69 @@ pub(crate) struct Command {
         env = "TRIM_SCAN_TARGET_FOLDER"
     )]
     scan_target_folder: bool,
+    #[arg(
+        long = "semver-compatible",
+        help = "Only consider crate old when newer semver compatible version is present for \
+                current command",
+        env = "TRIM_SEMVER_COMPATIBLE"
+    )]
+    semver_compatible: bool,
+    #[arg(
+        long = "size-mode",
+        value_enum,
+        default_value_t = SizeMode::Apparent,
+        global = true,
+        help = "How size is computed. Apparent size is length of files and disk size is space \
+                allocated on disk where hard linked file is counted only once",
+        value_name = "mode",
+        env = "TRIM_SIZE_MODE"
+    )]
+    size_mode: SizeMode,
     #[arg(
         long = "top",
         short = 't',
         help = "Show certain number of top crates which have highest size"
     )]
     top: Option<usize>,
+    #[arg(
+        long = "top-by",
+        value_enum,
+        default_value_t = TopOrder::Size,
+        help = "Order used by top. Last used order shows least recently used crates first",
+        value_name = "order",
+        requires = "top"
+    )]
+    top_by: TopOrder,
+    #[arg(
+        long = "trash",
+        global = true,
+        help = "Move removed files to trash directory inside $CARGO_HOME instead of deleting them \
+                so they can be restored using restore subcommand",
+        env = "TRIM_TRASH"
+    )]
+    trash: bool,
+    #[arg(
+        long = "unused-for",
+        help = "Clean crates which are not used for provided duration such as 90d or 6w. Without \
+                cargo global cache tracker last used time is download time of crate",
+        value_name = "duration",
+        value_parser = parse_duration
+    )]
+    unused_for: Option<Duration>,
     #[arg(
         long = "update",
         short = 'u',
         help = "Update Cargo.lock file present inside config directory folder path"
     )]
     update: bool,
+    #[arg(
+        long = "wait",
+        global = true,
+        help = "Wait for cargo package cache lock held by other process such as running cargo \
+                build instead of returning error",
+        env = "TRIM_WAIT"
+    )]
+    wait: bool,
     #[arg(long = "wipe", short = 'w', help = "Wipe folder", value_enum)]
     wipe: Option<Vec<Wipe>>,
     #[command(subcommand)]
@@ -165,6 +292,25 @@ enum Wipe {
     Src,
 }
 
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Table,
+    Json,
+    Ndjson,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq, Eq)]
+enum TopOrder {
+    Size,
+    LastUsed,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq, Eq)]
+enum SizeMode {
+    Apparent,
+    Disk,
+}
+
 #[derive(Clone, ValueEnum, Debug)]
 enum GitCompress {
     AggressiveCheckout,
@@ -181,14 +327,80 @@ enum GitCompressAction {
     Db,
 }
 
+impl SubCommand {
+    /// check if sub command modifies cargo cache
+    fn is_destructive(&self) -> bool {
+        match self {
+            Self::Git(git) => git.is_destructive(),
+            Self::Registry(registry) => registry.is_destructive(),
+            Self::Remove(remove) => remove.is_destructive(),
+            Self::Restore(restore) => restore.is_destructive(),
+            Self::Bin(bin) => bin.is_destructive(),
+            Self::Dedup(dedup) => dedup.is_destructive(),
+            Self::Verify(verify) => verify.is_destructive(),
+            _ => false,
+        }
+    }
+
+    /// check if sub command selects crates by last used time
+    fn uses_last_used(&self) -> bool {
+        match self {
+            Self::List(list) => list.uses_last_used(),
+            Self::Git(git) => git.uses_last_used(),
+            Self::Registry(registry) => registry.uses_last_used(),
+            _ => false,
+        }
+    }
+}
+
 impl Command {
+    /// check if command modifies cargo cache. Cargo package cache lock is
+    /// acquired before running such command
+    fn is_destructive(&self) -> bool {
+        let top_level = !self.dry_run
+            && (self.all
+                || self.clear_empty_index
+                || self.git_compress.is_some()
+                || self.light_cleanup
+                || self.max_size.is_some()
+                || self.old
+                || self.old_orphan
+                || self.orphan
+                || self.unused_for.is_some()
+                || self.wipe.is_some());
+        top_level || self.sub.as_ref().is_some_and(SubCommand::is_destructive)
+    }
+
+    /// check if command selects crates by last used time
+    fn uses_last_used(&self) -> bool {
+        self.unused_for.is_some()
+            || self.max_size.is_some()
+            || self.sub.as_ref().is_some_and(SubCommand::uses_last_used)
+    }
+
     #[expect(clippy::too_many_lines)]
     pub(crate) fn run(&self) -> Result<()> {
         let dry_run = self.dry_run;
+        set_machine_readable_output(self.format != OutputFormat::Table);
+        set_disk_size_mode(self.size_mode == SizeMode::Disk);
 
         // List all required path
         let dir_path = DirPath::new()?;
 
+        if self.trash {
+            enable_trash(dir_path.trash_dir());
+        }
+
+        // Acquire cargo package cache lock so running cargo doesn't extract crates
+        // which are getting removed
+        let mut package_cache_lock = PackageCacheLock::new(
+            dir_path.package_cache_lock_files(),
+            self.wait && !self.no_wait,
+        );
+        if self.is_destructive() {
+            package_cache_lock.acquire()?;
+        }
+
         // Read config file data
         let mut config_file = ConfigFile::init(dir_path.config_file())?;
 
@@ -205,6 +417,15 @@ impl Command {
                 config_file.add_ignore(ignore, dry_run, false)?;
             }
         }
+        if let Some(keep) = self.keep {
+            config_file.set_keep(Some(keep), dry_run, false)?;
+        }
+        if self.semver_compatible {
+            config_file.set_semver_compatible(true, dry_run, false)?;
+        }
+        if self.resolve_missing_locks {
+            config_file.set_resolve_missing_locks(true, dry_run, false)?;
+        }
         if self.no_scan_hidden_folder {
             config_file.set_scan_hidden_folder(false, dry_run, false)?;
         } else if self.scan_hidden_folder {
@@ -216,11 +437,43 @@ impl Command {
             config_file.set_scan_target_folder(true, dry_run, false)?;
         }
 
+        // Load scan cache of previous run. Cache subcommand manages cache file itself
+        // so cache is not used while running it
+        let scan_cache = ScanCache::load(
+            dir_path.scan_cache_file(),
+            !self.no_cache && !matches!(self.sub, Some(SubCommand::Cache(_))),
+        );
+
+        // cargo generate-lockfile waits for package cache lock so lock is released
+        // while missing Cargo.lock are resolved
+        let resolved_locks = if config_file.resolve_missing_locks() {
+            package_cache_lock.release();
+            let resolved_locks = resolve_missing_locks(&config_file, &scan_cache);
+            if self.is_destructive() {
+                package_cache_lock.acquire()?;
+            }
+            resolved_locks?
+        } else {
+            Vec::new()
+        };
+
         // create new CrateDetail struct
-        let mut crate_detail = CrateDetail::new(dir_path.index_dir(), dir_path.db_dir())?;
+        let mut crate_detail = CrateDetail::new(
+            dir_path.index_dir(),
+            dir_path.db_dir(),
+            dir_path.global_cache_file(),
+            SourceConfig::load(dir_path.cargo_config_files())?,
+        )?;
 
         // List crates (uses the already-mutated config)
-        let crate_list = CrateList::create_list(&dir_path, &config_file, &mut crate_detail)?;
+        let crate_list = CrateList::create_list(
+            &dir_path,
+            &config_file,
+            &mut crate_detail,
+            &resolved_locks,
+            &scan_cache,
+        )?;
+        scan_cache.save()?;
 
         if let Some(values) = &self.git_compress {
             for value in values {
@@ -259,36 +512,47 @@ impl Command {
             }
         }
 
+        // modify time of crate is time when crate was downloaded and it is not
+        // updated when crate is compiled again
+        if self.uses_last_used() && !crate_detail.has_usage_tracker() {
+            print_message(format_args!(
+                "{} cargo global cache tracker is not available so last used time of crate is \
+                 time when it was downloaded. Crate compiled recently can be treated as unused",
+                "Warning:".yellow()
+            ));
+        }
+
+        let mut report = Report::new(self.format, &crate_list, &crate_detail);
+
         if let Some(number) = self.top {
-            top_crates(&crate_detail, number);
+            top_crates(&mut report, number, self.top_by);
         }
 
         if self.update {
+            // cargo update acquires package cache lock itself
+            package_cache_lock.release();
             let cargo_lock_files = &crate_list.cargo_lock_files().paths();
             run_cargo_update_command(cargo_lock_files, dry_run)?;
+            if self.is_destructive() {
+                package_cache_lock.acquire()?;
+            }
         }
 
         if self.query {
-            query_size(&dir_path, &crate_list, &crate_detail);
+            query_size(&dir_path, &mut report);
         }
 
         let mut registry_crates_location =
             RegistryDir::new(dir_path.index_dir(), crate_list.installed_registry())?;
 
         if self.old {
-            old_clean(
-                &crate_list,
-                &mut registry_crates_location,
-                &crate_detail,
-                dry_run,
-            )?;
+            old_clean(&mut report, &mut registry_crates_location, dry_run)?;
         }
 
         if self.old_orphan {
             old_orphan_clean(
-                &crate_list,
+                &mut report,
                 &mut registry_crates_location,
-                &crate_detail,
                 config_file.directory().is_empty(),
                 dry_run,
             )?;
@@ -296,63 +560,72 @@ impl Command {
 
         if self.orphan {
             orphan_clean(
-                &crate_list,
+                &mut report,
                 &mut registry_crates_location,
-                &crate_detail,
                 config_file.directory().is_empty(),
                 dry_run,
             )?;
         }
 
-        if self.all {
-            remove_all(
-                &crate_list,
+        if let Some(duration) = self.unused_for {
+            unused_clean(
+                &mut report,
                 &mut registry_crates_location,
-                &crate_detail,
+                duration,
                 dry_run,
             )?;
         }
 
+        if let Some(max_size) = self.max_size {
+            max_size_clean(
+                &dir_path,
+                &mut report,
+                &mut registry_crates_location,
+                max_size,
+                dry_run,
+            )?;
+        }
+
+        if self.all {
+            remove_all(&mut report, &mut registry_crates_location, dry_run)?;
+        }
+
         if let Some(sub_command) = &self.sub {
             match &sub_command {
                 SubCommand::Init(init) => init.run(&mut config_file)?,
                 SubCommand::Clear(clear) => clear.run(&mut config_file)?,
                 SubCommand::Config(config) => config.run(&config_file, dir_path.config_file())?,
                 SubCommand::List(list) => {
-                    let max_width = std::cmp::max(
-                        crate_detail
-                            .source_infos()
-                            .keys()
-                            .map(String::len)
-                            .max()
-                            .unwrap_or(9),
-                        9,
-                    ) + 2;
-                    list.run(&crate_list, max_width, config_file.directory().is_empty());
+                    list.run(&mut report, config_file.directory().is_empty())?;
                 }
                 SubCommand::Set(set) => set.run(&mut config_file)?,
                 SubCommand::Unset(unset) => unset.run(&mut config_file)?,
                 SubCommand::Git(git) => {
-                    git.run(
-                        &dir_path,
-                        &crate_list,
-                        &crate_detail,
-                        config_file.directory().is_empty(),
-                    )?;
+                    git.run(&dir_path, &mut report, config_file.directory().is_empty())?;
                 }
                 SubCommand::Registry(registry) => {
                     registry.run(
                         &dir_path,
-                        &crate_list,
-                        &crate_detail,
+                        &mut report,
                         &mut registry_crates_location,
                         config_file.directory().is_empty(),
                     )?;
                 }
+                SubCommand::Remove(remove) => {
+                    remove.run(&mut report, &mut registry_crates_location)?;
+                }
+                SubCommand::Restore(restore) => restore.run(&dir_path)?,
+                SubCommand::Trash(trash) => trash.run(&dir_path)?,
+                SubCommand::Target(target) => target.run(&crate_list, &crate_detail)?,
+                SubCommand::Bin(bin) => bin.run(&dir_path)?,
+                SubCommand::Why(why) => why.run(&crate_list)?,
+                SubCommand::Cache(cache) => cache.run(&dir_path)?,
+                SubCommand::Dedup(dedup) => dedup.run(&dir_path, &mut report)?,
+                SubCommand::Verify(verify) => verify.run(&dir_path, &mut report)?,
             }
         }
 
-        Ok(())
+        report.finish()
     }
 }
 
@@ -388,9 +661,9 @@ fn clear_empty_index(
         is_success = delete_folder(index_dir.join(index).as_path(), dry_run).is_ok() && is_success;
     }
     if is_success {
-        println!("{}", "Cleaned empty index".blue());
+        print_message(format_args!("{}", "Cleaned empty index".blue()));
     } else {
-        println!("Failed to remove unused index");
+        print_message(format_args!("Failed to remove unused index"));
     }
 }
 
@@ -432,7 +705,7 @@ fn git_compress(
                         git_folder.push(".git");
                         if git_folder.exists() {
                             if !dry_run {
-                                println!(
+                                print_message(format_args!(
                                     "{}",
                                     format!(
                                         "Compressing {} registry index",
@@ -441,7 +714,7 @@ fn git_compress(
                                             .context("failed to get compress file name")?
                                     )
                                     .blue()
-                                );
+                                ));
                             }
                             run_git_compress_commands(&repo_path, dry_run, is_aggressive)?;
                         }
@@ -460,7 +733,10 @@ fn git_compress(
                             {
                                 let rev_path = rev?.path();
                                 if !dry_run {
-                                    println!("{}", "Compressing git checkout".blue());
+                                    print_message(format_args!(
+                                        "{}",
+                                        "Compressing git checkout".blue()
+                                    ));
                                 }
                                 run_git_compress_commands(&rev_path, dry_run, is_aggressive)?;
                             }
@@ -473,7 +749,7 @@ fn git_compress(
                     for entry in fs::read_dir(db_dir).context("failed to read db dir")? {
                         let repo_path = entry?.path();
                         if !dry_run {
-                            println!("{}", "Compressing git db".blue());
+                            print_message(format_args!("{}", "Compressing git db".blue()));
                         }
                         run_git_compress_commands(&repo_path, dry_run, is_aggressive)?;
                     }
@@ -481,18 +757,18 @@ fn git_compress(
             }
         }
     }
-    println!("{}", "Git compress task completed".blue());
+    print_message(format_args!("{}", "Git compress task completed".blue()));
     Ok(())
 }
 
 // run combination of commands which git compress a index of registry
 fn run_git_compress_commands(repo_path: &Path, dry_run: bool, is_aggressive: bool) -> Result<()> {
     if dry_run {
-        println!(
+        print_message(format_args!(
             "{} git compressing {}",
             "Dry run:".yellow(),
             repo_path.display()
-        );
+        ));
     } else {
         let mut commands = vec![
             // Pack unpacked objects in a repository
@@ -540,10 +816,10 @@ fn run_git_compress_commands(repo_path: &Path, dry_run: bool, is_aggressive: boo
                 let stderr = String::from_utf8_lossy(&output.stderr);
                 anyhow::bail!("git command at step {position}/{total_len} failed: {stderr}");
             }
-            println!(
+            print_message(format_args!(
                 "{:70}.......Step {position}/{total_len}",
                 format!("  {symbol} {message}")
-            );
+            ));
         }
     }
     Ok(())
@@ -557,7 +833,9 @@ fn light_cleanup(checkout_dir: &Path, src_dir: &Path, index_dir: &Path, dry_run:
     // light cleanup git
     light_cleanup_success = git::light_cleanup_git(checkout_dir, dry_run) && light_cleanup_success;
     if !light_cleanup_success {
-        println!("failed to delete some folder during light cleanup");
+        print_message(format_args!(
+            "failed to delete some folder during light cleanup"
+        ));
     }
 }
 
@@ -575,9 +853,9 @@ fn wipe_directory(wipe: &Wipe, dir_path: &DirPath, dry_run: bool) {
     }
     .is_err();
     if has_failed {
-        println!("Failed to remove {wipe:?} directory");
+        print_message(format_args!("Failed to remove {wipe:?} directory"));
     } else {
-        println!("{} {wipe:?} directory", "Removed".red());
+        print_message(format_args!("{} {wipe:?} directory", "Removed".red()));
     }
 }
 
@@ -588,11 +866,11 @@ fn run_cargo_update_command(cargo_lock_files: &[PathBuf], dry_run: bool) -> Resu
         };
         let location_str = location.display();
         if dry_run {
-            println!(
+            print_message(format_args!(
                 "{} Updating project at \"{}\"",
                 "Dry run:".yellow(),
                 location_str
-            );
+            ));
             // in dry run mode we will not actually update the cargo lock file but we will
             // run cargo update command in dry run mode
             if !std::process::Command::new("cargo")
@@ -608,7 +886,7 @@ fn run_cargo_update_command(cargo_lock_files: &[PathBuf], dry_run: bool) -> Resu
                 ));
             }
         } else {
-            println!("Updating project at {}", location_str.blue());
+            print_message(format_args!("Updating project at {}", location_str.blue()));
             if !std::process::Command::new("cargo")
                 .arg("update")
                 .current_dir(location)
@@ -620,78 +898,74 @@ fn run_cargo_update_command(cargo_lock_files: &[PathBuf], dry_run: bool) -> Resu
             }
         }
     }
-    println!("{}", "Successfully updated all dependencies".blue());
+    print_message(format_args!(
+        "{}",
+        "Successfully updated all dependencies".blue()
+    ));
     Ok(())
 }
 
 // show top n crates
-fn top_crates(crate_detail: &CrateDetail, number: usize) {
-    let max_width = std::cmp::max(
-        crate_detail
-            .source_infos()
-            .keys()
-            .map(String::len)
-            .max()
-            .unwrap_or(9),
-        9,
-    ) + 2;
-    show_top_number_crates(crate_detail.bin(), "bin", max_width, number);
-    registry::top_crates_registry(crate_detail, max_width, number);
-    git::top_crates_git(crate_detail, max_width, number);
+fn top_crates(report: &mut Report<'_>, number: usize, order: TopOrder) {
+    let crate_detail = report.crate_detail();
+    show_top_number_crates(report, crate_detail.bin(), "bin", number, order);
+    registry::top_crates_registry(report, number, order);
+    git::top_crates_git(report, number, order);
 }
 
 // query size of directory of cargo home folder provide some valuable size
 // information
-fn query_size(dir_path: &DirPath, crate_list: &CrateList, crate_detail: &CrateDetail) {
+fn query_size(dir_path: &DirPath, report: &mut Report<'_>) {
     let mut final_size = 0_u64;
     let bin_dir_size =
         get_inode_handled_size(dir_path.bin_dir(), &mut HashSet::new()).unwrap_or(0_u64);
     final_size += bin_dir_size;
-    query_print(
-        &format!(
-            "Total size of {} .cargo/bin binary:",
-            crate_list.installed_bin().len()
-        ),
-        &convert_pretty(bin_dir_size),
+    let bin_count = report.crate_list().installed_bin().len();
+    report.query(
+        &format!("Total size of {bin_count} .cargo/bin binary:"),
+        "bin",
+        Some(bin_count),
+        bin_dir_size,
     );
-    print_dash(query_full_width());
-    final_size += registry::query_size_registry(dir_path, crate_list, crate_detail);
-    final_size += git::query_size_git(dir_path, crate_list, crate_detail);
-    query_print("Total size", &convert_pretty(final_size));
+    report.query_separator();
+    final_size += registry::query_size_registry(dir_path, report);
+    final_size += git::query_size_git(dir_path, report);
+    report.query("Total size", "total", None, final_size);
 }
 
 // Clean old crates
 fn old_clean(
-    crate_list: &CrateList,
+    report: &mut Report<'_>,
     registry_crates_location: &mut RegistryDir,
-    crate_detail: &CrateDetail,
     dry_run: bool,
 ) -> Result<()> {
-    let (registry_sized_cleaned, total_registry_crate_removed) = clean_registry(
+    let crate_list = report.crate_list();
+    let crate_detail = report.crate_detail();
+    let removed_registry = clean_registry(
         registry_crates_location,
-        crate_list.old_registry(),
+        &crate_list.unprotected(crate_list.old_registry()),
         crate_detail,
         dry_run,
     )?;
-    let (git_sized_cleaned, total_git_crate_removed) =
-        clean_git(crate_list.old_git(), crate_detail, dry_run)?;
-    println!(
-        "{}",
-        format!(
-            "{} old crates removed which had occupied {}",
-            total_git_crate_removed + total_registry_crate_removed,
-            convert_pretty(git_sized_cleaned + registry_sized_cleaned)
-        )
-        .blue()
+    let removed_git = clean_git(
+        &crate_list.unprotected(crate_list.old_git()),
+        crate_detail,
+        dry_run,
+    )?;
+    report.removed(
+        "old",
+        dry_run,
+        &removed_registry,
+        &removed_git,
+        |count, size| format!("{count} old crates removed which had occupied {size}"),
     );
     Ok(())
 }
 
 // Clean out crates which is both old and orphan
 fn old_orphan_clean(
-    crate_list: &CrateList,
+    report: &mut Report<'_>,
     registry_crates_location: &mut RegistryDir,
-    crate_detail: &CrateDetail,
     directory_is_empty: bool,
     dry_run: bool,
 ) -> Result<()> {
@@ -701,47 +975,39 @@ fn old_orphan_clean(
                             not orphan crates. Run command 'cargo trim init' to initialize \
                             current directory as rust project directory or pass cargo trim set -d \
                             <directory> for setting rust project directory";
-        println!("{}", warning_text.yellow());
-        let mut input = String::new();
-        print!("Do you want to continue? (y/N) ");
-        std::io::stdout()
-            .flush()
-            .context("failed to flush output stream")?;
-        std::io::stdin()
-            .read_line(&mut input)
-            .context("error: unable to read user input")?;
-        let trimmed_input = input.trim().to_ascii_lowercase();
-        // if answer is any instead of yes and y return
-        if !["y", "yes"].contains(&trimmed_input.as_str()) {
+        if !confirm(warning_text)? {
             return Ok(());
         }
     }
-    let (registry_sized_cleaned, total_registry_crate_removed) = clean_registry(
+    let crate_list = report.crate_list();
+    let crate_detail = report.crate_detail();
+    let removed_registry = clean_registry(
         registry_crates_location,
-        &crate_list.old_orphan_registry(),
+        &crate_list.unprotected(&crate_list.old_orphan_registry()),
         crate_detail,
         dry_run,
     )?;
-    let (git_sized_cleaned, total_git_crate_removed) =
-        clean_git(&crate_list.old_orphan_git(), crate_detail, dry_run)?;
-
-    println!(
-        "{}",
-        format!(
-            "{} crates which are both old and orphan crate removed which had {}",
-            total_git_crate_removed + total_registry_crate_removed,
-            convert_pretty(git_sized_cleaned + registry_sized_cleaned)
-        )
-        .blue()
+    let removed_git = clean_git(
+        &crate_list.unprotected(&crate_list.old_orphan_git()),
+        crate_detail,
+        dry_run,
+    )?;
+    report.removed(
+        "old_orphan",
+        dry_run,
+        &removed_registry,
+        &removed_git,
+        |count, size| {
+            format!("{count} crates which are both old and orphan crate removed which had {size}")
+        },
     );
     Ok(())
 }
 
 // Clean orphan crates
 fn orphan_clean(
-    crate_list: &CrateList,
+    report: &mut Report<'_>,
     registry_crates_location: &mut RegistryDir,
-    crate_detail: &CrateDetail,
     directory_is_empty: bool,
     dry_run: bool,
 ) -> Result<()> {
@@ -751,66 +1017,162 @@ fn orphan_clean(
                             classified as orphan crate. Run command 'cargo trim init' to \
                             initialize current directory as rust project directory or pass cargo \
                             trim set -d <directory> for setting rust project directory";
-        println!("{}", warning_text.yellow());
-        let mut input = String::new();
-        print!("Do you want to continue? (y/N) ");
-        std::io::stdout()
-            .flush()
-            .context("failed to flush output stream")?;
-        std::io::stdin()
-            .read_line(&mut input)
-            .context("error: unable to read user input")?;
-        let trimmed_input = input.trim().to_ascii_lowercase();
-        // If answer is not y or yes then return
-        if !["y", "yes"].contains(&trimmed_input.as_str()) {
+        if !confirm(warning_text)? {
             return Ok(());
         }
     }
-    let (registry_sized_cleaned, total_registry_crate_removed) = clean_registry(
+    let crate_list = report.crate_list();
+    let crate_detail = report.crate_detail();
+    let removed_registry = clean_registry(
+        registry_crates_location,
+        &crate_list.unprotected(crate_list.orphan_registry()),
+        crate_detail,
+        dry_run,
+    )?;
+    let removed_git = clean_git(
+        &crate_list.unprotected(crate_list.orphan_git()),
+        crate_detail,
+        dry_run,
+    )?;
+    report.removed(
+        "orphan",
+        dry_run,
+        &removed_registry,
+        &removed_git,
+        |count, size| format!("{count} orphan crates removed which had occupied {size}"),
+    );
+    Ok(())
+}
+
+// Clean crates which are not used for provided duration
+fn unused_clean(
+    report: &mut Report<'_>,
+    registry_crates_location: &mut RegistryDir,
+    duration: Duration,
+    dry_run: bool,
+) -> Result<()> {
+    let crate_list = report.crate_list();
+    let crate_detail = report.crate_detail();
+    let removed_registry = clean_registry(
         registry_crates_location,
-        crate_list.orphan_registry(),
+        &crate_list.unprotected(&crate_list.unused_registry(duration)),
         crate_detail,
         dry_run,
     )?;
-    let (git_sized_cleaned, total_git_crate_removed) =
-        clean_git(crate_list.orphan_git(), crate_detail, dry_run)?;
+    let removed_git = clean_git(
+        &crate_list.unprotected(&crate_list.unused_git(duration)),
+        crate_detail,
+        dry_run,
+    )?;
+    report.removed(
+        "unused",
+        dry_run,
+        &removed_registry,
+        &removed_git,
+        |count, size| format!("{count} unused crates removed which had occupied {size}"),
+    );
+    Ok(())
+}
 
-    println!(
-        "{}",
-        format!(
-            "{} orphan crates removed which had occupied {}",
-            total_git_crate_removed + total_registry_crate_removed,
-            convert_pretty(git_sized_cleaned + registry_sized_cleaned)
-        )
-        .blue()
+// Clean least recently used crates until cache size is under max size
+fn max_size_clean(
+    dir_path: &DirPath,
+    report: &mut Report<'_>,
+    registry_crates_location: &mut RegistryDir,
+    max_size: u64,
+    dry_run: bool,
+) -> Result<()> {
+    // only crate directories are counted since index and other files of
+    // registry and git directory are never removed by max size clean
+    let current_size = report.crate_detail().total_dir_size(&[
+        dir_path.cache_dir(),
+        dir_path.src_dir(),
+        dir_path.checkout_dir(),
+        dir_path.db_dir(),
+    ])?;
+    if current_size <= max_size {
+        print_message(format_args!(
+            "{}",
+            format!(
+                "Cache size {} is already under {}",
+                convert_pretty(current_size),
+                convert_pretty(max_size)
+            )
+            .blue()
+        ));
+        return Ok(());
+    }
+    let crate_list = report.crate_list();
+    let crate_detail = report.crate_detail();
+    let (over_budget_registry, over_budget_git) =
+        crate_list.over_budget_crates(current_size, max_size);
+    if dry_run {
+        report.crates(
+            "over_budget",
+            &[
+                (
+                    "registry",
+                    "REGISTRY CRATE OVER BUDGET",
+                    &over_budget_registry,
+                ),
+                ("git", "GIT CRATE OVER BUDGET", &over_budget_git),
+            ],
+        );
+    }
+    let removed_registry = clean_registry(
+        registry_crates_location,
+        &over_budget_registry,
+        crate_detail,
+        dry_run,
+    )?;
+    let removed_git = clean_git(&over_budget_git, crate_detail, dry_run)?;
+    report.removed(
+        "max_size",
+        dry_run,
+        &removed_registry,
+        &removed_git,
+        |count, size| {
+            let size_cleaned = removed_registry
+                .iter()
+                .chain(&removed_git)
+                .map(CrateMetaData::size)
+                .sum::<u64>();
+            format!(
+                "{count} crates removed which had occupied {size}, cache size reduced from {} to \
+                 {}",
+                convert_pretty(current_size),
+                convert_pretty(current_size.saturating_sub(size_cleaned))
+            )
+        },
     );
     Ok(())
 }
 
 // remove all crates
 fn remove_all(
-    crate_list: &CrateList,
+    report: &mut Report<'_>,
     registry_crates_location: &mut RegistryDir,
-    crate_detail: &CrateDetail,
     dry_run: bool,
 ) -> Result<()> {
-    let (registry_sized_cleaned, total_registry_crate_removed) = clean_registry(
+    let crate_list = report.crate_list();
+    let crate_detail = report.crate_detail();
+    let removed_registry = clean_registry(
         registry_crates_location,
-        crate_list.installed_registry(),
+        &crate_list.unprotected(crate_list.installed_registry()),
         crate_detail,
         dry_run,
     )?;
-    let (git_sized_cleaned, total_git_crate_removed) =
-        clean_git(crate_list.installed_git(), crate_detail, dry_run)?;
-
-    println!(
-        "{}",
-        format!(
-            "Total size of {} crates removed :- {}",
-            total_git_crate_removed + total_registry_crate_removed,
-            convert_pretty(git_sized_cleaned + registry_sized_cleaned)
-        )
-        .blue()
+    let removed_git = clean_git(
+        &crate_list.unprotected(crate_list.installed_git()),
+        crate_detail,
+        dry_run,
+    )?;
+    report.removed(
+        "all",
+        dry_run,
+        &removed_registry,
+        &removed_git,
+        |count, size| format!("Total size of {count} crates removed :- {size}"),
     );
     Ok(())
 }
@@ -1,16 +1,15 @@
-use std::io::Write as _;
 use std::path::Path;
+use std::time::Duration;
 
-use anyhow::{Context as _, Result};
+use anyhow::Result;
 use clap::Parser;
-use owo_colors::OwoColorize as _;
 
-use super::utils::{print_dash, query_full_width, query_print, show_top_number_crates};
+use super::TopOrder;
+use super::utils::{Report, confirm, show_top_number_crates};
 use crate::crate_detail::{CrateDetail, CrateMetaData};
 use crate::dir_path::DirPath;
-use crate::list_crate::CrateList;
 use crate::registry_dir::RegistryDir;
-use crate::utils::{convert_pretty, get_size};
+use crate::utils::{parse_duration, print_message};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -63,58 +62,80 @@ pub(crate) struct Registry {
         value_name = "number"
     )]
     top: Option<usize>,
+    #[arg(
+        long = "top-by",
+        value_enum,
+        default_value_t = TopOrder::Size,
+        help = "Order used by top. Last used order shows least recently used crates first",
+        value_name = "order",
+        requires = "top"
+    )]
+    top_by: TopOrder,
+    #[arg(
+        long = "unused-for",
+        help = "Clean registry crates which are not used for provided duration such as 90d or 6w. \
+                Without cargo global cache tracker last used time is download time of crate",
+        value_name = "duration",
+        value_parser = parse_duration
+    )]
+    unused_for: Option<Duration>,
 }
 
 impl Registry {
+    /// check if command removes any file from cache
+    pub(super) fn is_destructive(&self) -> bool {
+        !self.dry_run
+            && (self.all
+                || self.light_cleanup
+                || self.old
+                || self.old_orphan
+                || self.orphan
+                || self.unused_for.is_some())
+    }
+
+    /// check if command selects crates by last used time
+    pub(super) fn uses_last_used(&self) -> bool {
+        self.unused_for.is_some()
+    }
+
     #[expect(clippy::too_many_lines)]
     pub(super) fn run(
         &self,
         dir_path: &DirPath,
-        crate_list: &CrateList,
-        crate_detail: &CrateDetail,
+        report: &mut Report<'_>,
         registry_crates_location: &mut RegistryDir,
         directory_is_empty: bool,
     ) -> Result<()> {
         let dry_run = self.dry_run;
+        let crate_list = report.crate_list();
+        let crate_detail = report.crate_detail();
         if self.light_cleanup {
             let light_cleanup_success =
                 light_cleanup_registry(dir_path.src_dir(), dir_path.index_dir(), dry_run);
             if !light_cleanup_success {
-                println!("Failed to delete some folder during light cleanup");
+                print_message(format_args!(
+                    "Failed to delete some folder during light cleanup"
+                ));
             }
         }
         if let Some(number) = self.top {
-            let max_width = std::cmp::max(
-                crate_detail
-                    .source_infos()
-                    .keys()
-                    .map(String::len)
-                    .max()
-                    .unwrap_or(9),
-                9,
-            ) + 2;
-            top_crates_registry(crate_detail, max_width, number);
+            top_crates_registry(report, number, self.top_by);
         }
         if self.query {
-            let final_size = query_size_registry(dir_path, crate_list, crate_detail);
-            query_print("Total size", &convert_pretty(final_size));
+            let final_size = query_size_registry(dir_path, report);
+            report.query("Total size", "total", None, final_size);
         }
 
         if self.old {
-            let (sized_cleaned, total_crate_removed) = clean_registry(
+            let removed_registry = clean_registry(
                 registry_crates_location,
-                crate_list.old_registry(),
+                &crate_list.unprotected(crate_list.old_registry()),
                 crate_detail,
                 dry_run,
             )?;
-            println!(
-                "{}",
-                format!(
-                    "{total_crate_removed} old crates removed which had occupied {}",
-                    convert_pretty(sized_cleaned)
-                )
-                .blue()
-            );
+            report.removed("old", dry_run, &removed_registry, &[], |count, size| {
+                format!("{count} old crates removed which had occupied {size}")
+            });
         }
 
         if self.old_orphan {
@@ -125,36 +146,27 @@ impl Registry {
                                     init' to initialize current directory as rust project \
                                     directory or pass cargo trim set -d <directory> for setting \
                                     rust project directory";
-                println!("{}", warning_text.yellow());
-                let mut input = String::new();
-                print!("Do you want to continue? (y/N) ");
-                std::io::stdout()
-                    .flush()
-                    .context("failed to flush output stream")?;
-                std::io::stdin()
-                    .read_line(&mut input)
-                    .context("error: unable to read user input")?;
-                let trimmed_input = input.trim().to_ascii_lowercase();
-                // if answer is any instead of yes and y return
-                if !["y", "yes"].contains(&trimmed_input.as_str()) {
+                if !confirm(warning_text)? {
                     return Ok(());
                 }
             }
-            let (sized_cleaned, total_crate_removed) = clean_registry(
+            let removed_registry = clean_registry(
                 registry_crates_location,
-                &crate_list.old_orphan_registry(),
+                &crate_list.unprotected(&crate_list.old_orphan_registry()),
                 crate_detail,
                 dry_run,
             )?;
-
-            println!(
-                "{}",
-                format!(
-                    "{total_crate_removed} crates which are both old and orphan crate removed \
-                     which had occupied {}",
-                    convert_pretty(sized_cleaned)
-                )
-                .blue()
+            report.removed(
+                "old_orphan",
+                dry_run,
+                &removed_registry,
+                &[],
+                |count, size| {
+                    format!(
+                        "{count} crates which are both old and orphan crate removed which had \
+                         occupied {size}"
+                    )
+                },
             );
         }
 
@@ -166,53 +178,43 @@ impl Registry {
                                     trim init' to initialize current directory as rust project \
                                     directory or pass cargo trim set -d <directory> for setting \
                                     rust project directory";
-                println!("{}", warning_text.yellow());
-                let mut input = String::new();
-                print!("Do you want to continue? (y/N) ");
-                std::io::stdout()
-                    .flush()
-                    .context("failed to flush output stream")?;
-                std::io::stdin()
-                    .read_line(&mut input)
-                    .context("error: unable to read user input")?;
-                let trimmed_input = input.trim().to_ascii_lowercase();
-                // If answer is not y or yes then return
-                if !["y", "yes"].contains(&trimmed_input.as_str()) {
+                if !confirm(warning_text)? {
                     return Ok(());
                 }
             }
-            let (sized_cleaned, total_crate_removed) = clean_registry(
+            let removed_registry = clean_registry(
                 registry_crates_location,
-                crate_list.orphan_registry(),
+                &crate_list.unprotected(crate_list.orphan_registry()),
                 crate_detail,
                 dry_run,
             )?;
+            report.removed("orphan", dry_run, &removed_registry, &[], |count, size| {
+                format!("{count} orphan crates removed which had occupied {size}")
+            });
+        }
 
-            println!(
-                "{}",
-                format!(
-                    "{total_crate_removed} orphan crates removed which had occupied {}",
-                    convert_pretty(sized_cleaned)
-                )
-                .blue()
-            );
+        if let Some(duration) = self.unused_for {
+            let removed_registry = clean_registry(
+                registry_crates_location,
+                &crate_list.unprotected(&crate_list.unused_registry(duration)),
+                crate_detail,
+                dry_run,
+            )?;
+            report.removed("unused", dry_run, &removed_registry, &[], |count, size| {
+                format!("{count} unused crates removed which had occupied {size}")
+            });
         }
 
         if self.all {
-            let (sized_cleaned, total_crate_removed) = clean_registry(
+            let removed_registry = clean_registry(
                 registry_crates_location,
-                crate_list.installed_registry(),
+                &crate_list.unprotected(crate_list.installed_registry()),
                 crate_detail,
                 dry_run,
             )?;
-            println!(
-                "{}",
-                format!(
-                    "Total size of {total_crate_removed} crates removed :- {}",
-                    convert_pretty(sized_cleaned)
-                )
-                .blue()
-            );
+            report.removed("all", dry_run, &removed_registry, &[], |count, size| {
+                format!("Total size of {count} crates removed :- {size}")
+            });
         }
 
         Ok(())
@@ -233,63 +235,76 @@ pub(super) fn light_cleanup_registry(src_dir: &Path, index_dir: &Path, dry_run:
 }
 
 // Show top registry crates
-pub(super) fn top_crates_registry(crate_detail: &CrateDetail, first_width: usize, number: usize) {
+pub(super) fn top_crates_registry(report: &mut Report<'_>, number: usize, order: TopOrder) {
+    let crate_detail = report.crate_detail();
     show_top_number_crates(
+        report,
         crate_detail.registry_crates_archive(),
         "registry_archive",
-        first_width,
         number,
+        order,
     );
     show_top_number_crates(
+        report,
         crate_detail.registry_crates_source(),
         "registry_source",
-        first_width,
         number,
+        order,
     );
 }
 
 // Query size of registry
-pub(super) fn query_size_registry(
-    dir_path: &DirPath,
-    crate_list: &CrateList,
-    crate_detail: &CrateDetail,
-) -> u64 {
-    let registry_dir_size = get_size(dir_path.registry_dir()).unwrap_or(0);
-    query_print(
-        &format!(
-            "Total size of {} .cargo/registry crates:",
-            crate_list.installed_registry().len()
-        ),
-        &convert_pretty(registry_dir_size),
+pub(super) fn query_size_registry(dir_path: &DirPath, report: &mut Report<'_>) -> u64 {
+    let registry_dir_size = report
+        .crate_detail()
+        .dir_size(dir_path.registry_dir())
+        .unwrap_or(0);
+    let registry_count = report.crate_list().installed_registry().len();
+    let archive_count = report.crate_detail().registry_crates_archive().len();
+    let source_count = report.crate_detail().registry_crates_source().len();
+    report.query(
+        &format!("Total size of {registry_count} .cargo/registry crates:"),
+        "registry",
+        Some(registry_count),
+        registry_dir_size,
     );
-    query_print(
-        &format!(
-            "   \u{251c} Size of {} .cargo/registry/cache folder",
-            crate_detail.registry_crates_archive().len()
-        ),
-        &convert_pretty(get_size(dir_path.cache_dir()).unwrap_or(0_u64)),
+    report.query(
+        &format!("   \u{251c} Size of {archive_count} .cargo/registry/cache folder"),
+        "registry_cache",
+        Some(archive_count),
+        report
+            .crate_detail()
+            .dir_size(dir_path.cache_dir())
+            .unwrap_or(0_u64),
     );
-    query_print(
+    report.query(
         "   \u{251c} Size of .cargo/registry/index folder",
-        &convert_pretty(get_size(dir_path.index_dir()).unwrap_or(0_u64)),
+        "registry_index",
+        None,
+        report
+            .crate_detail()
+            .dir_size(dir_path.index_dir())
+            .unwrap_or(0_u64),
     );
-    query_print(
-        &format!(
-            "   \u{2514} Size of {} .cargo/registry/src folder",
-            crate_detail.registry_crates_source().len()
-        ),
-        &convert_pretty(get_size(dir_path.src_dir()).unwrap_or(0_u64)),
+    report.query(
+        &format!("   \u{2514} Size of {source_count} .cargo/registry/src folder"),
+        "registry_src",
+        Some(source_count),
+        report
+            .crate_detail()
+            .dir_size(dir_path.src_dir())
+            .unwrap_or(0_u64),
     );
-    print_dash(query_full_width());
+    report.query_separator();
     registry_dir_size
 }
 
-// perform clean on git crates
+// perform clean on registry crates and return removed crates
 pub(super) fn clean_registry(
     registry_crates_location: &mut RegistryDir,
     crate_metadata_list: &[CrateMetaData],
     crate_detail: &CrateDetail,
     dry_run: bool,
-) -> Result<(u64, usize)> {
+) -> Result<Vec<CrateMetaData>> {
     registry_crates_location.remove_crate_list(crate_detail, crate_metadata_list, dry_run)
 }
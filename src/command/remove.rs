@@ -0,0 +1,122 @@
+use anyhow::Result;
+use clap::Parser;
+use owo_colors::OwoColorize as _;
+
+use super::git::clean_git;
+use super::registry::clean_registry;
+use super::utils::Report;
+use crate::crate_detail::CrateMetaData;
+use crate::crate_spec::{CrateSpec, wildcard_match};
+use crate::registry_dir::RegistryDir;
+use crate::utils::print_message;
+
+#[derive(Debug, Parser)]
+#[command(
+    about = "Remove specific registry and git crates",
+    arg_required_else_help = true
+)]
+pub(crate) struct Remove {
+    #[arg(
+        long = "dry-run",
+        short = 'n',
+        help = "Run command in dry run mode to see what would be done"
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "source",
+        short = 's',
+        help = "Only remove crates of provided registry index or git repository directory name \
+                such as index.crates.io-1949cf8c6b5b557f. Wildcard is supported",
+        value_name = "index"
+    )]
+    source: Vec<String>,
+    #[arg(
+        help = "Crate to remove such as serde, serde@1.0.188, serde@^1 or windows-*. Checkout \
+                name of git crate is also accepted",
+        value_name = "spec",
+        required = true
+    )]
+    specs: Vec<String>,
+}
+
+impl Remove {
+    /// check if command removes any file from cache
+    pub(super) fn is_destructive(&self) -> bool {
+        !self.dry_run
+    }
+
+    pub(super) fn run(
+        &self,
+        report: &mut Report<'_>,
+        registry_crates_location: &mut RegistryDir,
+    ) -> Result<()> {
+        let dry_run = self.dry_run;
+        let specs = self
+            .specs
+            .iter()
+            .map(|spec| CrateSpec::parse(spec))
+            .collect::<Result<Vec<_>>>()?;
+        let crate_list = report.crate_list();
+        let crate_detail = report.crate_detail();
+        let matched_registry = self.matched_crates(&specs, crate_list.installed_registry());
+        let matched_git = self.matched_crates(&specs, crate_list.installed_git());
+        for (spec, value) in specs.iter().zip(&self.specs) {
+            if !matched_registry
+                .iter()
+                .chain(&matched_git)
+                .any(|crate_metadata| spec.matches(crate_metadata))
+            {
+                print_message(format_args!(
+                    "{} no installed crate matches {value}",
+                    "Warning:".yellow()
+                ));
+            }
+        }
+        for crate_metadata in matched_registry
+            .iter()
+            .chain(&matched_git)
+            .filter(|crate_metadata| crate_list.is_protected(crate_metadata))
+        {
+            let name = match crate_metadata.version() {
+                Some(version) => format!("{}-{version}", crate_metadata.name()),
+                None => crate_metadata.name().clone(),
+            };
+            print_message(format_args!(
+                "{} {name} since it is protected",
+                "Skipped".yellow()
+            ));
+        }
+        let removed_registry = clean_registry(
+            registry_crates_location,
+            &crate_list.unprotected(&matched_registry),
+            crate_detail,
+            dry_run,
+        )?;
+        let removed_git = clean_git(&crate_list.unprotected(&matched_git), crate_detail, dry_run)?;
+        report.removed(
+            "remove",
+            dry_run,
+            &removed_registry,
+            &removed_git,
+            |count, size| format!("{count} crates removed which had occupied {size}"),
+        );
+        Ok(())
+    }
+
+    /// list crates which match with any spec and source filter
+    fn matched_crates(&self, specs: &[CrateSpec], crates: &[CrateMetaData]) -> Vec<CrateMetaData> {
+        crates
+            .iter()
+            .filter(|crate_metadata| specs.iter().any(|spec| spec.matches(crate_metadata)))
+            .filter(|crate_metadata| {
+                self.source.is_empty()
+                    || crate_metadata.source().is_some_and(|source| {
+                        self.source
+                            .iter()
+                            .any(|pattern| wildcard_match(pattern, source))
+                    })
+            })
+            .cloned()
+            .collect()
+    }
+}
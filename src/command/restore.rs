@@ -0,0 +1,128 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::Result;
+use clap::Parser;
+use owo_colors::OwoColorize as _;
+
+use crate::dir_path::DirPath;
+use crate::registry_dir::index_cache_location;
+use crate::trash::{Trash, TrashEntry};
+use crate::utils::{print_message, split_name_version};
+
+#[derive(Debug, Parser)]
+#[command(
+    about = "Restore files which were moved to trash directory by --trash",
+    arg_required_else_help = true
+)]
+pub(crate) struct Restore {
+    #[arg(
+        long = "all",
+        short = 'a',
+        help = "Restore all entries present in trash"
+    )]
+    all: bool,
+    #[arg(
+        long = "dry-run",
+        short = 'n',
+        help = "Run command in dry run mode to see what would be done"
+    )]
+    dry_run: bool,
+    #[arg(
+        help = "Crate name or trash entry id which should be restored",
+        value_name = "crate",
+        conflicts_with = "all"
+    )]
+    crates: Vec<String>,
+}
+
+impl Restore {
+    /// check if command moves any file back to cache
+    pub(super) fn is_destructive(&self) -> bool {
+        !self.dry_run
+    }
+
+    pub(super) fn run(&self, dir_path: &DirPath) -> Result<()> {
+        let dry_run = self.dry_run;
+        let mut trash = Trash::load(dir_path.trash_dir())?;
+        let filter =
+            |entry: &TrashEntry| self.all || self.crates.iter().any(|name| entry.matches(name));
+        let restored = trash.restore(filter, dry_run)?;
+        for entry in &restored {
+            if dry_run {
+                print_message(format_args!(
+                    "{} {} {}",
+                    "Dry run:".yellow(),
+                    "Restored".green(),
+                    entry.original_path().display()
+                ));
+            } else {
+                print_message(format_args!(
+                    "{} {}",
+                    "Restored".green(),
+                    entry.original_path().display()
+                ));
+            }
+        }
+        for entry in trash.entries().iter().filter(|entry| {
+            filter(entry)
+                && !restored
+                    .iter()
+                    .any(|restored_entry| restored_entry.id() == entry.id())
+        }) {
+            print_message(format_args!(
+                "Skipped restoring {} since it is already present",
+                entry.original_path().display()
+            ));
+        }
+        print_missing_index_cache(dir_path, &restored)?;
+        print_message(format_args!(
+            "{}",
+            format!("{} entries restored from trash", restored.len()).blue()
+        ));
+        Ok(())
+    }
+}
+
+/// print registry crates whose index cache is not present after restore. Cargo
+/// refetches missing index cache entry when crate is required next time
+fn print_missing_index_cache(dir_path: &DirPath, restored: &[TrashEntry]) -> Result<()> {
+    let mut missing = BTreeSet::new();
+    for entry in restored {
+        let original_path = entry.original_path();
+        let Some(parent) = original_path.parent() else {
+            continue;
+        };
+        let registry_parent = parent.parent();
+        if registry_parent != Some(dir_path.src_dir().as_path())
+            && registry_parent != Some(dir_path.cache_dir().as_path())
+        {
+            continue;
+        }
+        let (Some(index_name), Some(file_name)) = (
+            parent.file_name().and_then(|name| name.to_str()),
+            original_path.file_name().and_then(|name| name.to_str()),
+        ) else {
+            continue;
+        };
+        let Ok((name, _)) = split_name_version(file_name) else {
+            continue;
+        };
+        let location =
+            index_cache_location(&dir_path.index_dir().join(index_name).join(".cache"), &name)?;
+        if !location.exists()
+            && !restored
+                .iter()
+                .any(|restored_entry| restored_entry.original_path() == Path::new(&location))
+        {
+            missing.insert(format!("{index_name}/{name}"));
+        }
+    }
+    for name in missing {
+        print_message(format_args!(
+            "Index cache of {name} is not present in trash, it will be refetched by cargo when \
+             required"
+        ));
+    }
+    Ok(())
+}
@@ -4,6 +4,7 @@ use clap::Parser;
 use crate::config_file::ConfigFile;
 #[derive(Debug, Parser)]
 #[command(about = "Set config file values", arg_required_else_help = true)]
+#[expect(clippy::struct_excessive_bools)]
 pub(crate) struct Set {
     #[arg(
         long = "dry-run",
@@ -26,10 +27,32 @@ pub(crate) struct Set {
         value_name = "path"
     )]
     ignore: Option<Vec<String>>,
+    #[arg(
+        long = "keep",
+        help = "Set number of newest versions of each crate which are not considered old",
+        value_name = "number",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    keep: Option<usize>,
+    #[arg(
+        long = "protect",
+        short = 'p',
+        help = "Add crate spec such as openssl-src, serde@^1 or aws-sdk-* or git repository url \
+                to protect list. Protected crates are never removed by cleaning commands",
+        value_name = "spec"
+    )]
+    protect: Option<Vec<String>>,
+    #[arg(
+        long = "resolve-missing-locks",
+        help = "Set resolve missing locks as true"
+    )]
+    resolve_missing_locks: bool,
     #[arg(long = "scan-hidden-folder", help = "Set scan hidden folder as true")]
     scan_hidden_folder: bool,
     #[arg(long = "scan-target-folder", help = "Set scan hidden folder as true")]
     scan_target_folder: bool,
+    #[arg(long = "semver-compatible", help = "Set semver compatible as true")]
+    semver_compatible: bool,
 }
 
 impl Set {
@@ -50,12 +73,26 @@ impl Set {
                 config_file.add_ignore(ignore, dry_run, true)?;
             }
         }
+        if let Some(keep) = self.keep {
+            config_file.set_keep(Some(keep), dry_run, true)?;
+        }
+        if let Some(protects) = &self.protect {
+            for protect in protects {
+                config_file.add_protect(protect, dry_run, true)?;
+            }
+        }
+        if self.resolve_missing_locks {
+            config_file.set_resolve_missing_locks(true, dry_run, true)?;
+        }
         if self.scan_hidden_folder {
             config_file.set_scan_hidden_folder(true, dry_run, true)?;
         }
         if self.scan_target_folder {
             config_file.set_scan_target_folder(true, dry_run, true)?;
         }
+        if self.semver_compatible {
+            config_file.set_semver_compatible(true, dry_run, true)?;
+        }
 
         Ok(())
     }
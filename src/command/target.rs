@@ -0,0 +1,239 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use clap::Parser;
+use owo_colors::OwoColorize as _;
+
+use crate::crate_detail::CrateDetail;
+use crate::list_crate::CrateList;
+use crate::target_dir::{Profile, TargetDir, list_target_dirs};
+use crate::utils::{convert_pretty, delete_folder, parse_duration, print_message};
+
+#[derive(Debug, Parser)]
+#[command(
+    about = "Perform operation to target directory of Rust projects",
+    arg_required_else_help = true
+)]
+#[expect(clippy::struct_excessive_bools)]
+pub(crate) struct Target {
+    #[arg(
+        long = "dry-run",
+        short = 'n',
+        help = "Run command in dry run mode to see what would be done"
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "list",
+        short = 'l',
+        help = "List target directory of projects with size and last build time"
+    )]
+    list: bool,
+    #[arg(
+        long = "old-profile",
+        short = 'p',
+        help = "Remove build profile directory such as debug or release which are not built for \
+                unused-for duration instead of whole target directory",
+        requires = "unused_for"
+    )]
+    old_profile: bool,
+    #[arg(
+        long = "old-toolchain",
+        short = 't',
+        help = "Remove fingerprints and artifacts which were built by toolchain other than \
+                toolchain currently used by project"
+    )]
+    old_toolchain: bool,
+    #[arg(
+        long = "unused-for",
+        help = "Only operate on target directory which are not built for provided duration such \
+                as 30d or 4w",
+        value_name = "duration",
+        value_parser = parse_duration
+    )]
+    unused_for: Option<Duration>,
+    #[arg(long = "wipe", short = 'w', help = "Remove whole target directory")]
+    wipe: bool,
+}
+
+impl Target {
+    pub(super) fn run(&self, crate_list: &CrateList, crate_detail: &CrateDetail) -> Result<()> {
+        let dry_run = self.dry_run;
+        let target_dirs = list_target_dirs(
+            crate_list.cargo_lock_files().paths(),
+            crate_detail.source_config(),
+        )?;
+        let now = SystemTime::now();
+        let is_unused = |last_build: Option<SystemTime>| {
+            self.unused_for.is_none_or(|duration| {
+                last_build.is_none_or(|time| {
+                    now.duration_since(time)
+                        .is_ok_and(|elapsed| elapsed >= duration)
+                })
+            })
+        };
+        // profile are filtered individually when removing old profile
+        let selected_target_dirs = target_dirs
+            .iter()
+            .filter(|target_dir| self.old_profile || is_unused(target_dir.last_build()))
+            .collect::<Vec<_>>();
+
+        if self.list {
+            list_targets(&selected_target_dirs, now);
+        }
+
+        if self.wipe {
+            wipe_targets(&selected_target_dirs, dry_run);
+        } else if self.old_profile {
+            remove_old_profiles(&selected_target_dirs, is_unused, dry_run);
+        }
+
+        if self.old_toolchain && !self.wipe {
+            remove_old_toolchain_artifacts(&selected_target_dirs, dry_run)?;
+        }
+        Ok(())
+    }
+}
+
+// list target directory with their size and last build time
+fn list_targets(target_dirs: &[&TargetDir], now: SystemTime) {
+    let first_width = std::cmp::max(
+        target_dirs
+            .iter()
+            .map(|target_dir| target_dir.path().display().to_string().len())
+            .max()
+            .unwrap_or(30),
+        30,
+    );
+    let mut total_size = 0;
+    for target_dir in target_dirs {
+        total_size += target_dir.size();
+        let last_build = target_dir.last_build().map_or_else(
+            || String::from("never"),
+            |time| {
+                format!(
+                    "{} days ago",
+                    now.duration_since(time).unwrap_or_default().as_secs() / 86_400
+                )
+            },
+        );
+        print_message(format_args!(
+            "{:first_width$} {:>10} {last_build:>14}",
+            target_dir.path().display().to_string(),
+            convert_pretty(target_dir.size()),
+        ));
+        for project in target_dir.projects() {
+            print_message(format_args!("   \u{2514} {}", project.display()));
+        }
+    }
+    print_message(format_args!(
+        "{}",
+        format!(
+            "Total size of {} target directories :- {}",
+            target_dirs.len(),
+            convert_pretty(total_size)
+        )
+        .blue()
+    ));
+}
+
+// remove whole target directories
+fn wipe_targets(target_dirs: &[&TargetDir], dry_run: bool) {
+    let mut total_size = 0;
+    let mut total_removed = 0;
+    for target_dir in target_dirs {
+        if target_dir.profiles().iter().any(Profile::is_building) {
+            print_message(format_args!(
+                "Skipped {} since cargo is building it",
+                target_dir.path().display()
+            ));
+            continue;
+        }
+        if delete_folder(target_dir.path(), dry_run).is_ok() {
+            total_size += target_dir.size();
+            total_removed += 1;
+        } else {
+            print_message(format_args!(
+                "Failed to remove {}",
+                target_dir.path().display()
+            ));
+        }
+    }
+    print_message(format_args!(
+        "{}",
+        format!(
+            "{total_removed} target directories removed which had occupied {}",
+            convert_pretty(total_size)
+        )
+        .blue()
+    ));
+}
+
+// remove profile directories which are not built for long time
+fn remove_old_profiles(
+    target_dirs: &[&TargetDir],
+    is_unused: impl Fn(Option<SystemTime>) -> bool,
+    dry_run: bool,
+) {
+    let mut total_removed = 0;
+    for target_dir in target_dirs {
+        for profile in target_dir.profiles() {
+            if !is_unused(profile.last_build()) {
+                continue;
+            }
+            if profile.is_building() {
+                print_message(format_args!(
+                    "Skipped {} since cargo is building it",
+                    profile.path().display()
+                ));
+                continue;
+            }
+            if delete_folder(profile.path(), dry_run).is_ok() {
+                total_removed += 1;
+            } else {
+                print_message(format_args!(
+                    "Failed to remove {}",
+                    profile.path().display()
+                ));
+            }
+        }
+    }
+    print_message(format_args!(
+        "{}",
+        format!("{total_removed} old profile directories removed").blue()
+    ));
+}
+
+// remove fingerprints and artifacts built by toolchain other than toolchain
+// currently used by projects
+fn remove_old_toolchain_artifacts(target_dirs: &[&TargetDir], dry_run: bool) -> Result<()> {
+    let mut total_removed = 0;
+    for target_dir in target_dirs {
+        let rustc_hashes = target_dir.current_rustc_hashes()?;
+        if rustc_hashes.is_empty() {
+            continue;
+        }
+        for profile in target_dir.profiles() {
+            // profile may have been removed as old profile
+            if !profile.path().exists() {
+                continue;
+            }
+            if profile.is_building() {
+                print_message(format_args!(
+                    "Skipped {} since cargo is building it",
+                    profile.path().display()
+                ));
+                continue;
+            }
+            for artifact in profile.artifacts_built_by_other_rustc(&rustc_hashes)? {
+                if delete_folder(&artifact, dry_run).is_ok() {
+                    total_removed += 1;
+                }
+            }
+        }
+    }
+    print_message(format_args!(
+        "{}",
+        format!("{total_removed} artifacts of previous toolchain removed").blue()
+    ));
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use clap::{ArgGroup, Parser};
+use owo_colors::OwoColorize as _;
+
+use crate::dir_path::DirPath;
+use crate::trash::TrashEntry;
+use crate::utils::{convert_pretty, get_size, parse_duration, print_message};
+
+#[derive(Debug, Parser)]
+#[command(
+    about = "Manage files which were moved to trash directory by --trash",
+    arg_required_else_help = true
+)]
+pub(crate) struct Trash {
+    #[command(subcommand)]
+    sub: TrashSubCommand,
+}
+
+#[derive(Debug, Parser)]
+enum TrashSubCommand {
+    List(List),
+    Purge(Purge),
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "List all entries present in trash")]
+struct List;
+
+#[derive(Debug, Parser)]
+#[command(
+    about = "Permanently remove entries from trash",
+    arg_required_else_help = true,
+    group(ArgGroup::new("purge").required(true).args(["all", "older_than"]))
+)]
+struct Purge {
+    #[arg(long = "all", short = 'a', help = "Purge all entries present in trash")]
+    all: bool,
+    #[arg(
+        long = "dry-run",
+        short = 'n',
+        help = "Run command in dry run mode to see what would be done"
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "older-than",
+        help = "Purge entries which were moved to trash before provided duration such as 30d",
+        value_name = "duration",
+        value_parser = parse_duration
+    )]
+    older_than: Option<Duration>,
+}
+
+impl Trash {
+    pub(super) fn run(&self, dir_path: &DirPath) -> Result<()> {
+        let mut trash = crate::trash::Trash::load(dir_path.trash_dir())?;
+        match &self.sub {
+            TrashSubCommand::List(_) => {
+                let mut total_size = 0;
+                for entry in trash.entries() {
+                    let size = get_size(&entry.trashed_path(trash.trash_dir())).unwrap_or(0);
+                    total_size += size;
+                    print_message(format_args!(
+                        "{} {:>10} {:>10} {}",
+                        entry.id().blue(),
+                        convert_pretty(size),
+                        format!("{} days ago", entry_age(entry).as_secs() / 86_400),
+                        entry.original_path().display()
+                    ));
+                }
+                print_message(format_args!(
+                    "{}",
+                    format!(
+                        "Total size of {} trash entries :- {}",
+                        trash.entries().len(),
+                        convert_pretty(total_size)
+                    )
+                    .blue()
+                ));
+            }
+            TrashSubCommand::Purge(purge) => {
+                let dry_run = purge.dry_run;
+                let filter = |entry: &TrashEntry| {
+                    purge.all
+                        || purge
+                            .older_than
+                            .is_some_and(|duration| entry_age(entry) >= duration)
+                };
+                let mut total_size = 0;
+                for entry in trash.entries().iter().filter(|entry| filter(entry)) {
+                    total_size += get_size(&entry.trashed_path(trash.trash_dir())).unwrap_or(0);
+                    if dry_run {
+                        print_message(format_args!(
+                            "{} {} {}",
+                            "Dry run:".yellow(),
+                            "Purged".red(),
+                            entry.original_path().display()
+                        ));
+                    } else {
+                        print_message(format_args!(
+                            "{} {}",
+                            "Purged".red(),
+                            entry.original_path().display()
+                        ));
+                    }
+                }
+                let purged = trash.purge(filter, dry_run)?;
+                print_message(format_args!(
+                    "{}",
+                    format!(
+                        "{} trash entries purged which had occupied {}",
+                        purged.len(),
+                        convert_pretty(total_size)
+                    )
+                    .blue()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// return duration since entry was moved to trash
+fn entry_age(entry: &TrashEntry) -> Duration {
+    SystemTime::now()
+        .duration_since(entry.trashed_at())
+        .unwrap_or_default()
+}
@@ -4,6 +4,7 @@ use clap::Parser;
 use crate::config_file::ConfigFile;
 #[derive(Debug, Parser)]
 #[command(about = "Unset values from config file", arg_required_else_help = true)]
+#[expect(clippy::struct_excessive_bools)]
 pub(crate) struct Unset {
     #[arg(
         long = "dry-run",
@@ -24,10 +25,29 @@ pub(crate) struct Unset {
         value_name = "path"
     )]
     ignore: Option<Vec<String>>,
+    #[arg(
+        long = "keep",
+        help = "Reset number of newest versions of each crate which are not considered old to 1"
+    )]
+    keep: bool,
+    #[arg(
+        long = "protect",
+        short = 'p',
+        help = "Crate spec or git url to be removed from protect list in config file",
+        value_name = "spec"
+    )]
+    protect: Option<Vec<String>>,
+    #[arg(
+        long = "resolve-missing-locks",
+        help = "Set resolve missing locks as false"
+    )]
+    resolve_missing_locks: bool,
     #[arg(long = "scan-hidden-folder", help = "Set scan hidden folder as false")]
     scan_hidden_folder: bool,
     #[arg(long = "scan-target-folder", help = "Set scan hidden folder as false")]
     scan_target_folder: bool,
+    #[arg(long = "semver-compatible", help = "Set semver compatible as false")]
+    semver_compatible: bool,
 }
 
 impl Unset {
@@ -47,12 +67,26 @@ impl Unset {
                 config_file.remove_ignore(ignore, dry_run, true)?;
             }
         }
+        if self.keep {
+            config_file.set_keep(None, dry_run, true)?;
+        }
+        if let Some(protects) = &self.protect {
+            for protect in protects {
+                config_file.remove_protect(protect, dry_run, true)?;
+            }
+        }
+        if self.resolve_missing_locks {
+            config_file.set_resolve_missing_locks(false, dry_run, true)?;
+        }
         if self.scan_hidden_folder {
             config_file.set_scan_hidden_folder(false, dry_run, true)?;
         }
         if self.scan_target_folder {
             config_file.set_scan_target_folder(false, dry_run, true)?;
         }
+        if self.semver_compatible {
+            config_file.set_semver_compatible(false, dry_run, true)?;
+        }
 
         Ok(())
     }
@@ -1,12 +1,381 @@
 use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
 
+use anyhow::{Context as _, Result};
 use owo_colors::OwoColorize as _;
+use serde::Serialize;
+use url::Url;
 
-use crate::crate_detail::CrateMetaData;
-use crate::utils::convert_pretty;
+use super::{OutputFormat, TopOrder};
+use crate::crate_detail::{CrateDetail, CrateMetaData};
+use crate::list_crate::CrateList;
+use crate::project::Project;
+use crate::utils::{convert_pretty, print_message, print_prompt};
+
+/// suffix shown after name of protected crate in table
+const PROTECTED_SUFFIX: &str = " (protected)";
+
+/// Stores crate information in machine readable form
+#[derive(Serialize)]
+struct CrateRecord {
+    kind: String,
+    name: String,
+    version: Option<String>,
+    source: Option<String>,
+    source_url: Option<Url>,
+    size: u64,
+    /// last used time as seconds since unix epoch
+    last_used: Option<u64>,
+    path: Option<PathBuf>,
+    classification: Vec<&'static str>,
+}
+
+/// Machine readable record printed for json and ndjson format
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record {
+    Crate {
+        report: String,
+        #[serde(flatten)]
+        detail: Box<CrateRecord>,
+    },
+    Query {
+        name: String,
+        count: Option<usize>,
+        size: u64,
+    },
+    Removed {
+        action: String,
+        dry_run: bool,
+        count: usize,
+        size: u64,
+        crates: Vec<CrateRecord>,
+    },
+    Dedup {
+        dry_run: bool,
+        linked: usize,
+        reclaimed: u64,
+    },
+    Project {
+        root: PathBuf,
+        members: Vec<PathBuf>,
+        lock_file: Option<PathBuf>,
+        ignored_lock_files: Vec<PathBuf>,
+        registry_dependencies: usize,
+        git_dependencies: usize,
+        pinned_size: u64,
+    },
+}
+
+/// Print crate lists, query sizes and removal summaries in requested output
+/// format. Table format is printed immediately, ndjson format prints a record
+/// per line and json format prints all records as single array on finish
+pub(super) struct Report<'a> {
+    format: OutputFormat,
+    crate_list: &'a CrateList,
+    crate_detail: &'a CrateDetail,
+    records: Vec<Record>,
+}
+
+impl<'a> Report<'a> {
+    /// create new report
+    pub(super) fn new(
+        format: OutputFormat,
+        crate_list: &'a CrateList,
+        crate_detail: &'a CrateDetail,
+    ) -> Self {
+        Self {
+            format,
+            crate_list,
+            crate_detail,
+            records: Vec::new(),
+        }
+    }
+
+    /// return crate list used by report
+    pub(super) fn crate_list(&self) -> &'a CrateList {
+        self.crate_list
+    }
+
+    /// return crate detail used by report
+    pub(super) fn crate_detail(&self) -> &'a CrateDetail {
+        self.crate_detail
+    }
+
+    /// width of location column of table
+    fn first_width(&self) -> usize {
+        std::cmp::max(
+            self.crate_detail
+                .source_infos()
+                .keys()
+                .map(String::len)
+                .max()
+                .unwrap_or(9),
+            9,
+        ) + 2
+    }
+
+    /// print lists of crates. Each list is provided as kind, table title and
+    /// crates. All tables share same width
+    pub(super) fn crates(&mut self, report: &str, lists: &[(&str, &str, &[CrateMetaData])]) {
+        if self.format == OutputFormat::Table {
+            let first_width = self.first_width();
+            let second_width = std::cmp::max(
+                lists
+                    .iter()
+                    .flat_map(|(_, _, crates)| crates.iter())
+                    .map(|cm| {
+                        let protected_len = if self.crate_list.is_protected(cm) {
+                            PROTECTED_SUFFIX.len()
+                        } else {
+                            0
+                        };
+                        if let Some(version) = cm.version() {
+                            cm.name().len() + version.to_string().len() + 1 + protected_len
+                        } else {
+                            cm.name().len() + protected_len
+                        }
+                    })
+                    .max()
+                    .unwrap_or(30),
+                30,
+            ) + 2;
+            for (_, title, crates) in lists {
+                crate_list_type(crates, self.crate_list, first_width, second_width, title);
+            }
+        } else {
+            for (kind, _, crates) in lists {
+                for crate_metadata in *crates {
+                    let detail = Box::new(self.crate_record(kind, crate_metadata));
+                    self.push(Record::Crate {
+                        report: report.to_string(),
+                        detail,
+                    });
+                }
+            }
+        }
+    }
+
+    /// print detected projects along with their members, Cargo.lock files
+    /// and dependency counts
+    pub(super) fn projects(&mut self, projects: &[Project]) {
+        if self.format != OutputFormat::Table {
+            for project in projects {
+                self.push(Record::Project {
+                    root: project.root().to_path_buf(),
+                    members: project.members().to_vec(),
+                    lock_file: project.lock_file().cloned(),
+                    ignored_lock_files: project.ignored_lock_files().to_vec(),
+                    registry_dependencies: project.registry_dependency_count(),
+                    git_dependencies: project.git_dependency_count(),
+                    pinned_size: project.pinned_size(),
+                });
+            }
+            return;
+        }
+        print_message(format_args!(
+            "{}",
+            format!("Total detected projects: {}", projects.len()).blue()
+        ));
+        for (index, project) in projects.iter().enumerate() {
+            let kind = if project.members().is_empty() {
+                String::from("package")
+            } else {
+                format!("workspace with {} members", project.members().len())
+            };
+            print_message(format_args!(
+                "{}: {} ({kind})",
+                format!("Project [{index}]").blue(),
+                project.root().display()
+            ));
+            print_message(format_args!(
+                "    registry dependencies: {}, git dependencies: {}, pinned cache size: {}",
+                project.registry_dependency_count(),
+                project.git_dependency_count(),
+                convert_pretty(project.pinned_size())
+            ));
+            if project.lock_file().is_none() {
+                print_message(format_args!("    {}", "no Cargo.lock".yellow()));
+            }
+            for member in project.members() {
+                print_message(format_args!("    member: {}", member.display()));
+            }
+            for lock_file in project.ignored_lock_files() {
+                print_message(format_args!(
+                    "    {}: {}",
+                    "ignored Cargo.lock".yellow(),
+                    lock_file.display()
+                ));
+            }
+        }
+    }
+
+    /// print query size line
+    pub(super) fn query(&mut self, label: &str, name: &str, count: Option<usize>, size: u64) {
+        if self.format == OutputFormat::Table {
+            query_print(label, &convert_pretty(size));
+        } else {
+            self.push(Record::Query {
+                name: name.to_string(),
+                count,
+                size,
+            });
+        }
+    }
+
+    /// print separator between query sections
+    pub(super) fn query_separator(&self) {
+        if self.format == OutputFormat::Table {
+            print_dash(query_full_width());
+        }
+    }
+
+    /// print summary of removed registry and git crates. Table message is
+    /// created from total number of crates and pretty size removed
+    pub(super) fn removed(
+        &mut self,
+        action: &str,
+        dry_run: bool,
+        removed_registry: &[CrateMetaData],
+        removed_git: &[CrateMetaData],
+        table_message: impl FnOnce(usize, String) -> String,
+    ) {
+        let count = removed_registry.len() + removed_git.len();
+        let size = removed_registry
+            .iter()
+            .chain(removed_git)
+            .map(CrateMetaData::size)
+            .sum();
+        if self.format == OutputFormat::Table {
+            println!("{}", table_message(count, convert_pretty(size)).blue());
+        } else {
+            let crates = removed_registry
+                .iter()
+                .map(|crate_metadata| self.crate_record("registry", crate_metadata))
+                .chain(
+                    removed_git
+                        .iter()
+                        .map(|crate_metadata| self.crate_record("git", crate_metadata)),
+                )
+                .collect();
+            self.push(Record::Removed {
+                action: action.to_string(),
+                dry_run,
+                count,
+                size,
+                crates,
+            });
+        }
+    }
+
+    /// print summary of duplicate files replaced by hard link
+    pub(super) fn dedup(&mut self, dry_run: bool, linked: usize, reclaimed: u64) {
+        if self.format == OutputFormat::Table {
+            let message = format!(
+                "{linked} duplicate files linked which reclaimed {}",
+                convert_pretty(reclaimed)
+            );
+            if dry_run {
+                print_message(format_args!("{} {message}", "Dry run:".yellow()));
+            } else {
+                print_message(format_args!("{}", message.blue()));
+            }
+        } else {
+            self.push(Record::Dedup {
+                dry_run,
+                linked,
+                reclaimed,
+            });
+        }
+    }
+
+    /// print all collected records for json format
+    pub(super) fn finish(&self) -> Result<()> {
+        if self.format == OutputFormat::Json {
+            let content = serde_json::to_string_pretty(&self.records)
+                .context("failed to convert report to json")?;
+            println!("{content}");
+        }
+        Ok(())
+    }
+
+    /// print or collect record according to format
+    fn push(&mut self, record: Record) {
+        if self.format == OutputFormat::Ndjson {
+            match serde_json::to_string(&record) {
+                Ok(content) => println!("{content}"),
+                Err(err) => print_message(format_args!("failed to convert record to json {err}")),
+            }
+        } else {
+            self.records.push(record);
+        }
+    }
+
+    /// create machine readable crate record. Kind starting with registry or git
+    /// is classified using registry or git crate list
+    fn crate_record(&self, kind: &str, crate_metadata: &CrateMetaData) -> CrateRecord {
+        let crate_list = self.crate_list;
+        let (installed, old, orphan) = if kind.starts_with("registry") {
+            (
+                crate_list.installed_registry(),
+                crate_list.old_registry(),
+                crate_list.orphan_registry(),
+            )
+        } else if kind.starts_with("git") {
+            (
+                crate_list.installed_git(),
+                crate_list.old_git(),
+                crate_list.orphan_git(),
+            )
+        } else {
+            (crate_list.installed_bin(), &Vec::new(), &Vec::new())
+        };
+        let mut classification = Vec::new();
+        for (name, list) in [("installed", installed), ("old", old), ("orphan", orphan)] {
+            if list.binary_search(crate_metadata).is_ok() {
+                classification.push(name);
+            }
+        }
+        if crate_list.is_protected(crate_metadata) {
+            classification.push("protected");
+        }
+        CrateRecord {
+            kind: kind.to_string(),
+            name: crate_metadata.name().clone(),
+            version: crate_metadata.version().map(ToString::to_string),
+            source: crate_metadata.source().cloned(),
+            source_url: crate_metadata
+                .source()
+                .and_then(|source| self.crate_detail.source_infos().get(source))
+                .cloned(),
+            size: crate_metadata.size(),
+            last_used: crate_metadata
+                .last_used()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs()),
+            path: crate_metadata.path().cloned(),
+            classification,
+        }
+    }
+}
+
+/// print warning and ask user for confirmation. Return true if user want to
+/// continue
+pub(super) fn confirm(warning_text: &str) -> Result<bool> {
+    print_message(format_args!("{}", warning_text.yellow()));
+    let mut input = String::new();
+    print_prompt("Do you want to continue? (y/N) ")?;
+    std::io::stdin()
+        .read_line(&mut input)
+        .context("error: unable to read user input")?;
+    let trimmed_input = input.trim().to_ascii_lowercase();
+    // If answer is not y or yes then do not continue
+    Ok(["y", "yes"].contains(&trimmed_input.as_str()))
+}
 
 /// show title
-pub(super) fn show_title(
+fn show_title(
     title: &str,
     first_width: usize,
     second_width: usize,
@@ -24,7 +393,7 @@ pub(super) fn show_title(
 }
 
 /// show total count using data and size
-pub(super) fn show_total_count(
+fn show_total_count(
     data: &[CrateMetaData],
     size: u64,
     first_width: usize,
@@ -51,47 +420,43 @@ pub(super) fn show_total_count(
 }
 
 /// print dash
-pub(super) fn print_dash(len: usize) {
+fn print_dash(len: usize) {
     println!("{}", "-".repeat(len));
 }
 
 /// top crates help to List top n crates
 pub(super) fn show_top_number_crates(
+    report: &mut Report<'_>,
     crates: &HashSet<CrateMetaData>,
     crate_type: &str,
-    first_width: usize,
     number: usize,
+    order: TopOrder,
 ) {
-    // sort crates by size
     let mut crates = crates.iter().collect::<Vec<_>>();
-    crates.sort_by_key(|a| std::cmp::Reverse(a.size()));
     let top_number = std::cmp::min(crates.len(), number);
-    let title = format!("Top {top_number} {crate_type}");
-    let mut listed_crates = Vec::new();
-    for &crate_metadata in crates.iter().take(top_number) {
-        listed_crates.push(crate_metadata.clone());
-    }
-    let top_number_crates = &listed_crates[..top_number];
-    let second_width = std::cmp::max(
-        top_number_crates
-            .iter()
-            .map(|cm| {
-                if let Some(version) = cm.version() {
-                    cm.name().len() + version.to_string().len() + 1
-                } else {
-                    cm.name().len()
-                }
-            })
-            .max()
-            .unwrap_or(30),
-        30,
-    ) + 2;
-    crate_list_type(top_number_crates, first_width, second_width, &title);
+    let title = match order {
+        TopOrder::Size => {
+            crates.sort_by_key(|a| std::cmp::Reverse(a.size()));
+            format!("Top {top_number} {crate_type}")
+        }
+        TopOrder::LastUsed => {
+            // crate without known last used time is shown last
+            crates.sort_by_key(|a| (a.last_used().is_none(), a.last_used()));
+            format!("Top {top_number} least recently used {crate_type}")
+        }
+    };
+    let top_number_crates = crates
+        .into_iter()
+        .take(top_number)
+        .cloned()
+        .collect::<Vec<_>>();
+    report.crates("top", &[(crate_type, &title, &top_number_crates)]);
 }
 
 // list certain crate type to terminal
-pub(super) fn crate_list_type(
+fn crate_list_type(
     crate_metadata_list: &[CrateMetaData],
+    crate_list: &CrateList,
     first_width: usize,
     second_width: usize,
     title: &str,
@@ -104,6 +469,11 @@ pub(super) fn crate_list_type(
     for crate_metadata in crate_metadata_list {
         let size = crate_metadata.size();
         total_size += size;
+        let protected = if crate_list.is_protected(crate_metadata) {
+            PROTECTED_SUFFIX
+        } else {
+            ""
+        };
         if let Some(version) = crate_metadata.version() {
             println!(
                 "|{:^first_width$}|{:^second_width$}|{:^third_width$}|",
@@ -111,7 +481,7 @@ pub(super) fn crate_list_type(
                     .source()
                     .as_ref()
                     .map_or("N/A".to_string(), ToString::to_string),
-                format!("{}-{version}", crate_metadata.name()),
+                format!("{}-{version}{protected}", crate_metadata.name()),
                 convert_pretty(size)
             );
         } else {
@@ -121,7 +491,7 @@ pub(super) fn crate_list_type(
                     .source()
                     .as_ref()
                     .map_or("N/A".to_string(), ToString::to_string),
-                crate_metadata.name(),
+                format!("{}{protected}", crate_metadata.name()),
                 convert_pretty(size)
             );
         }
@@ -141,13 +511,46 @@ fn query_param_widths() -> (usize, usize) {
 }
 
 /// Get full width of query length
-pub(super) fn query_full_width() -> usize {
+fn query_full_width() -> usize {
     let (a, b) = query_param_widths();
     a + b + 1
 }
 
 /// Print query first and second params
-pub(super) fn query_print(first_param: &str, second_param: &str) {
+fn query_print(first_param: &str, second_param: &str) {
     let (first_path_width, second_path_width) = query_param_widths();
     println!("{first_param:first_path_width$} {second_param:>second_path_width$}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Record;
+
+    #[test]
+    fn removed_record_json_test() {
+        let record = Record::Removed {
+            action: "old".to_string(),
+            dry_run: true,
+            count: 0,
+            size: 0,
+            crates: Vec::new(),
+        };
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"type":"removed","action":"old","dry_run":true,"count":0,"size":0,"crates":[]}"#
+        );
+    }
+
+    #[test]
+    fn query_record_json_test() {
+        let record = Record::Query {
+            name: "registry".to_string(),
+            count: Some(2),
+            size: 8000,
+        };
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"type":"query","name":"registry","count":2,"size":8000}"#
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Write as _;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use owo_colors::OwoColorize as _;
+use rayon::prelude::*;
+
+use super::utils::Report;
+use crate::crate_detail::{CrateDetail, CrateMetaData};
+use crate::dir_path::DirPath;
+use crate::list_crate::CrateList;
+use crate::registry_dir::index_cache_checksum;
+use crate::utils::{delete_folder, print_message, sha256_file};
+
+#[derive(Debug, Parser)]
+#[command(
+    about = "Verify checksum of registry .crate archives against Cargo.lock files and index cache"
+)]
+pub(crate) struct Verify {
+    #[arg(
+        long = "dry-run",
+        short = 'n',
+        help = "Run command in dry run mode to see what would be done"
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "purge",
+        short = 'p',
+        help = "Remove archive whose checksum doesn't match along with its extracted source so \
+                cargo downloads it again"
+    )]
+    purge: bool,
+}
+
+impl Verify {
+    /// check if command modifies cargo cache
+    pub(super) fn is_destructive(&self) -> bool {
+        self.purge && !self.dry_run
+    }
+
+    pub(super) fn run(&self, dir_path: &DirPath, report: &mut Report<'_>) -> Result<()> {
+        let crate_list = report.crate_list();
+        let crate_detail = report.crate_detail();
+        let mut archives = crate_detail
+            .registry_crates_archive()
+            .iter()
+            .filter(|crate_metadata| crate_metadata.path().is_some())
+            .collect::<Vec<_>>();
+        archives.sort();
+        let hashed = archives
+            .par_iter()
+            .filter_map(|crate_metadata| {
+                let path = crate_metadata.path()?;
+                Some((
+                    *crate_metadata,
+                    sha256_file(path).map(|digest| to_hex(&digest)),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let (mut valid, mut corrupt, mut unknown) = (0, 0, 0);
+        let mut purged = Vec::new();
+        for (crate_metadata, checksum) in hashed {
+            // unreadable archive cannot be used by cargo so it is corrupt
+            let checksum = match checksum {
+                Ok(checksum) => checksum,
+                Err(err) => {
+                    corrupt += 1;
+                    print_message(format_args!(
+                        "{} {} cannot be read: {}",
+                        "Corrupt".red(),
+                        display_name(crate_metadata),
+                        err.root_cause()
+                    ));
+                    if self.purge {
+                        self.purge_crate(crate_metadata, crate_detail)?;
+                        purged.push(crate_metadata.clone());
+                    }
+                    continue;
+                }
+            };
+            let expected = expected_checksums(dir_path, crate_list, crate_metadata)?;
+            if expected.is_empty() {
+                unknown += 1;
+                continue;
+            }
+            let mismatched = expected
+                .iter()
+                .filter(|(expected, _)| !expected.eq_ignore_ascii_case(&checksum))
+                .collect::<Vec<_>>();
+            if mismatched.is_empty() {
+                valid += 1;
+                continue;
+            }
+            corrupt += 1;
+            print_message(format_args!(
+                "{} {} has checksum {checksum}",
+                "Corrupt".red(),
+                display_name(crate_metadata)
+            ));
+            for (expected, origin) in mismatched {
+                print_message(format_args!("    expected {expected} by {origin}"));
+            }
+            if self.purge {
+                self.purge_crate(crate_metadata, crate_detail)?;
+                purged.push(crate_metadata.clone());
+            }
+        }
+
+        print_message(format_args!(
+            "{}",
+            format!(
+                "{} archives verified: {valid} valid, {corrupt} corrupt, {unknown} without known \
+                 checksum",
+                valid + corrupt + unknown
+            )
+            .blue()
+        ));
+        if self.purge {
+            report.removed("verify", self.dry_run, &purged, &[], |count, size| {
+                format!("{count} corrupt crate archives removed which had occupied {size}")
+            });
+        } else if corrupt > 0 {
+            bail!("found {corrupt} corrupt crate archives. Run verify with --purge to remove them");
+        }
+        Ok(())
+    }
+
+    /// remove corrupt archive and its extracted source
+    fn purge_crate(
+        &self,
+        crate_metadata: &CrateMetaData,
+        crate_detail: &CrateDetail,
+    ) -> Result<()> {
+        let source_path = crate_detail
+            .registry_crates_source()
+            .get(crate_metadata)
+            .and_then(CrateMetaData::path);
+        for path in crate_metadata.path().into_iter().chain(source_path) {
+            delete_folder(path, self.dry_run)?;
+        }
+        if self.dry_run {
+            print_message(format_args!(
+                "{} {} {}",
+                "Dry run:".yellow(),
+                "Removed".red(),
+                display_name(crate_metadata)
+            ));
+        } else {
+            print_message(format_args!(
+                "{} {}",
+                "Removed".red(),
+                display_name(crate_metadata)
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// list checksums of crate recorded in scanned Cargo.lock files and index
+/// cache along with place where checksum was recorded
+fn expected_checksums(
+    dir_path: &DirPath,
+    crate_list: &CrateList,
+    crate_metadata: &CrateMetaData,
+) -> Result<Vec<(String, String)>> {
+    let mut expected = crate_list
+        .lock_checksums(crate_metadata)
+        .iter()
+        .map(|(checksum, lock_file)| (checksum.clone(), lock_file.display().to_string()))
+        .collect::<Vec<_>>();
+    if let (Some(source), Some(version)) = (crate_metadata.source(), crate_metadata.version()) {
+        let index_cache_dir = dir_path.index_dir().join(source).join(".cache");
+        if let Some(checksum) =
+            index_cache_checksum(&index_cache_dir, crate_metadata.name(), version)?
+        {
+            expected.push((checksum, "index cache".to_string()));
+        }
+    }
+    Ok(expected)
+}
+
+/// return display name of crate along with version and source
+fn display_name(crate_metadata: &CrateMetaData) -> String {
+    let mut name = crate_metadata.name().clone();
+    if let Some(version) = crate_metadata.version() {
+        name = format!("{name}-{version}");
+    }
+    if let Some(source) = crate_metadata.source() {
+        name = format!("{name} [{source}]");
+    }
+    name
+}
+
+/// convert digest to lowercase hex string
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
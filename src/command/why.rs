@@ -0,0 +1,115 @@
+use anyhow::Result;
+use clap::Parser;
+use owo_colors::OwoColorize as _;
+
+use crate::crate_detail::CrateMetaData;
+use crate::crate_spec::CrateSpec;
+use crate::list_crate::{CrateList, lock_file_dependency_paths};
+use crate::utils::{convert_pretty, print_message};
+
+#[derive(Debug, Parser)]
+#[command(
+    about = "Show which projects use crate and why",
+    arg_required_else_help = true
+)]
+pub(crate) struct Why {
+    #[arg(
+        help = "Crate name with optional version such as serde, serde@1.0.188 or serde-1.0.188. \
+                Checkout name of git crate is also accepted",
+        value_name = "crate"
+    )]
+    spec: String,
+}
+
+impl Why {
+    pub(super) fn run(&self, crate_list: &CrateList) -> Result<()> {
+        let spec = CrateSpec::parse(&self.spec)?;
+        show_installed_status(crate_list, &spec, &self.spec);
+        let mut referenced = false;
+        for project in crate_list.projects() {
+            for lock_file in project.read_lock_files() {
+                let paths = lock_file_dependency_paths(lock_file, &spec)?;
+                if paths.is_empty() {
+                    continue;
+                }
+                referenced = true;
+                let ignored = if project.ignored_lock_files().contains(lock_file) {
+                    " (ignored by cargo)"
+                } else {
+                    ""
+                };
+                print_message(format_args!(
+                    "{} {} via {}{ignored}",
+                    "Used by".blue(),
+                    project.root().display(),
+                    lock_file.display()
+                ));
+                for path in paths {
+                    print_message(format_args!("    {}", path.join(" -> ")));
+                }
+            }
+        }
+        if !referenced {
+            print_message(format_args!(
+                "{} is not referenced by any project",
+                self.spec.yellow()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// show whether installed crates matched by spec are old, orphan or both
+fn show_installed_status(crate_list: &CrateList, spec: &CrateSpec, spec_value: &str) {
+    let mut installed = false;
+    for (crate_metadata, old_list, orphan_list) in [
+        (
+            crate_list.installed_registry(),
+            crate_list.old_registry(),
+            crate_list.orphan_registry(),
+        ),
+        (
+            crate_list.installed_git(),
+            crate_list.old_git(),
+            crate_list.orphan_git(),
+        ),
+    ]
+    .into_iter()
+    .flat_map(|(crates, old_list, orphan_list)| {
+        crates
+            .iter()
+            .filter(|crate_metadata| spec.matches(crate_metadata))
+            .map(move |crate_metadata| (crate_metadata, old_list, orphan_list))
+    }) {
+        installed = true;
+        let status = match (
+            old_list.contains(crate_metadata),
+            orphan_list.contains(crate_metadata),
+        ) {
+            (true, true) => "old and orphan",
+            (true, false) => "old",
+            (false, true) => "orphan",
+            (false, false) => "in use",
+        };
+        print_message(format_args!(
+            "{} ({}) is {status}",
+            display_name(crate_metadata),
+            convert_pretty(crate_metadata.size())
+        ));
+    }
+    if !installed {
+        print_message(format_args!("No installed crate matches {spec_value}"));
+    }
+}
+
+/// return display name of crate along with version and source
+fn display_name(crate_metadata: &CrateMetaData) -> String {
+    let mut name = crate_metadata.name().clone();
+    if let Some(version) = crate_metadata.version() {
+        name = format!("{name} v{version}");
+    }
+    if let Some(source) = crate_metadata.source() {
+        name = format!("{name} [{source}]");
+    }
+    name
+}
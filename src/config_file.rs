@@ -8,9 +8,13 @@ use owo_colors::OwoColorize as _;
 use serde::{Deserialize, Serialize};
 
 use crate::list_crate::CargoLockFiles;
+use crate::protect::validate_protect_entry;
+use crate::scan_cache::{DirListing, ScanCache, Stamp};
+use crate::utils::print_message;
 
 /// Stores config file information
 #[derive(Serialize, Deserialize, Default)]
+#[expect(clippy::struct_excessive_bools)]
 pub(crate) struct ConfigFile {
     #[serde(default)]
     directory: Vec<String>,
@@ -20,6 +24,14 @@ pub(crate) struct ConfigFile {
     scan_hidden_folder: bool,
     #[serde(default)]
     scan_target_folder: bool,
+    #[serde(default)]
+    keep: Option<usize>,
+    #[serde(default)]
+    protect: Vec<String>,
+    #[serde(default)]
+    semver_compatible: bool,
+    #[serde(default)]
+    resolve_missing_locks: bool,
     #[serde(skip)]
     location: PathBuf,
 }
@@ -53,6 +65,11 @@ impl ConfigFile {
         &self.ignore
     }
 
+    /// return vector of protected crate specs and git urls
+    pub(crate) fn protect(&self) -> &Vec<String> {
+        &self.protect
+    }
+
     /// scan hidden folder
     pub(crate) fn scan_hidden_folder(&self) -> bool {
         self.scan_hidden_folder
@@ -63,6 +80,100 @@ impl ConfigFile {
         self.scan_target_folder
     }
 
+    /// number of newest versions of each crate which are not considered old.
+    /// At least one version is always kept
+    pub(crate) fn keep(&self) -> usize {
+        self.keep.unwrap_or(1).max(1)
+    }
+
+    /// only consider crate old when it is superseded by semver compatible
+    /// version
+    pub(crate) fn semver_compatible(&self) -> bool {
+        self.semver_compatible
+    }
+
+    /// Set semver compatible to value
+    pub(crate) fn set_semver_compatible(
+        &mut self,
+        value: bool,
+        dry_run: bool,
+        save: bool,
+    ) -> Result<()> {
+        // value which is not saved only overrides current command so it is
+        // applied even in dry run
+        if !dry_run || !save {
+            self.semver_compatible = value;
+        }
+        if dry_run {
+            print_message(format_args!(
+                "{} Set semver_compatible to {value:?}",
+                "Dry run:".yellow()
+            ));
+        } else {
+            if save {
+                self.save()?;
+            }
+            print_message(format_args!("Set semver_compatible to {value:?}"));
+        }
+        Ok(())
+    }
+
+    /// generate temporary Cargo.lock for workspaces which don't have one so
+    /// their dependencies are not considered orphan
+    pub(crate) fn resolve_missing_locks(&self) -> bool {
+        self.resolve_missing_locks
+    }
+
+    /// Set resolve missing locks to value
+    pub(crate) fn set_resolve_missing_locks(
+        &mut self,
+        value: bool,
+        dry_run: bool,
+        save: bool,
+    ) -> Result<()> {
+        // value which is not saved only overrides current command so it is
+        // applied even in dry run
+        if !dry_run || !save {
+            self.resolve_missing_locks = value;
+        }
+        if dry_run {
+            print_message(format_args!(
+                "{} Set resolve_missing_locks to {value:?}",
+                "Dry run:".yellow()
+            ));
+        } else {
+            if save {
+                self.save()?;
+            }
+            print_message(format_args!("Set resolve_missing_locks to {value:?}"));
+        }
+        Ok(())
+    }
+
+    /// Set number of newest versions to keep. Passing none resets to default
+    pub(crate) fn set_keep(
+        &mut self,
+        value: Option<usize>,
+        dry_run: bool,
+        save: bool,
+    ) -> Result<()> {
+        let keep = value.unwrap_or(1);
+        // value which is not saved only overrides current command so it is
+        // applied even in dry run
+        if !dry_run || !save {
+            self.keep = value;
+        }
+        if dry_run {
+            print_message(format_args!("{} Set keep to {keep}", "Dry run:".yellow()));
+        } else {
+            if save {
+                self.save()?;
+            }
+            print_message(format_args!("Set keep to {keep}"));
+        }
+        Ok(())
+    }
+
     /// Set scan hidden folder to value
     pub(crate) fn set_scan_hidden_folder(
         &mut self,
@@ -71,16 +182,16 @@ impl ConfigFile {
         save: bool,
     ) -> Result<()> {
         if dry_run {
-            println!(
+            print_message(format_args!(
                 "{} Set scan_hidden_folder to {value:?}",
                 "Dry run:".yellow(),
-            );
+            ));
         } else {
             self.scan_hidden_folder = value;
             if save {
                 self.save()?;
             }
-            println!("Set scan_hidden_folder to {value:?}");
+            print_message(format_args!("Set scan_hidden_folder to {value:?}"));
         }
         Ok(())
     }
@@ -93,16 +204,16 @@ impl ConfigFile {
         save: bool,
     ) -> Result<()> {
         if dry_run {
-            println!(
+            print_message(format_args!(
                 "{} Set scan_target_folder to {value:?}",
                 "Dry run:".yellow(),
-            );
+            ));
         } else {
             self.scan_target_folder = value;
             if save {
                 self.save()?;
             }
-            println!("Set scan_target_folder to {value:?}");
+            print_message(format_args!("Set scan_target_folder to {value:?}"));
         }
         Ok(())
     }
@@ -110,13 +221,13 @@ impl ConfigFile {
     /// add directory
     pub(crate) fn add_directory(&mut self, path: &str, dry_run: bool, save: bool) -> Result<()> {
         if dry_run {
-            println!("{} Added {path:?}", "Dry run:".yellow());
+            print_message(format_args!("{} Added {path:?}", "Dry run:".yellow()));
         } else {
             self.directory.push(path.to_string());
             if save {
                 self.save()?;
             }
-            println!("{} {path:?}", "Added".red());
+            print_message(format_args!("{} {path:?}", "Added".red()));
         }
         Ok(())
     }
@@ -124,13 +235,33 @@ impl ConfigFile {
     /// add ignore entry which is a relative or absolute path
     pub(crate) fn add_ignore(&mut self, ignore: &str, dry_run: bool, save: bool) -> Result<()> {
         if dry_run {
-            println!("{} Added {ignore:?}", "Dry run:".yellow());
+            print_message(format_args!("{} Added {ignore:?}", "Dry run:".yellow()));
         } else {
             self.ignore.push(ignore.to_string());
             if save {
                 self.save()?;
             }
-            println!("{} {ignore:?}", "Added".red());
+            print_message(format_args!("{} {ignore:?}", "Added".red()));
+        }
+        Ok(())
+    }
+
+    /// add protect entry which is crate spec or git url
+    pub(crate) fn add_protect(&mut self, protect: &str, dry_run: bool, save: bool) -> Result<()> {
+        validate_protect_entry(protect)?;
+        if dry_run {
+            print_message(format_args!(
+                "{} Protected {protect:?}",
+                "Dry run:".yellow()
+            ));
+        } else {
+            if !self.protect.iter().any(|data| data == protect) {
+                self.protect.push(protect.to_string());
+            }
+            if save {
+                self.save()?;
+            }
+            print_message(format_args!("{} {protect:?}", "Protected".green()));
         }
         Ok(())
     }
@@ -138,13 +269,17 @@ impl ConfigFile {
     /// remove directory
     pub(crate) fn remove_directory(&mut self, path: &str, dry_run: bool, save: bool) -> Result<()> {
         if dry_run {
-            println!("{} {} {path:?}", "Dry run:".yellow(), "Removed".red());
+            print_message(format_args!(
+                "{} {} {path:?}",
+                "Dry run:".yellow(),
+                "Removed".red()
+            ));
         } else {
             self.directory.retain(|data| data != path);
             if save {
                 self.save()?;
             }
-            println!("{} {path:?}", "Removed".red());
+            print_message(format_args!("{} {path:?}", "Removed".red()));
         }
         Ok(())
     }
@@ -152,40 +287,98 @@ impl ConfigFile {
     /// remove ignore entry
     pub(crate) fn remove_ignore(&mut self, ignore: &str, dry_run: bool, save: bool) -> Result<()> {
         if dry_run {
-            println!("{} {} {ignore:?}", "Dry run:".yellow(), "Removed".red());
+            print_message(format_args!(
+                "{} {} {ignore:?}",
+                "Dry run:".yellow(),
+                "Removed".red()
+            ));
         } else {
             self.ignore.retain(|data| data != ignore);
             if save {
                 self.save()?;
             }
-            println!("{} {ignore:?}", "Removed".red());
+            print_message(format_args!("{} {ignore:?}", "Removed".red()));
+        }
+        Ok(())
+    }
+
+    /// remove protect entry
+    pub(crate) fn remove_protect(
+        &mut self,
+        protect: &str,
+        dry_run: bool,
+        save: bool,
+    ) -> Result<()> {
+        if dry_run {
+            print_message(format_args!(
+                "{} {} {protect:?}",
+                "Dry run:".yellow(),
+                "Unprotected".red()
+            ));
+        } else {
+            self.protect.retain(|data| data != protect);
+            if save {
+                self.save()?;
+            }
+            print_message(format_args!("{} {protect:?}", "Unprotected".red()));
         }
         Ok(())
     }
 
     /// List Cargo.lock file present directories by recursively analyze all
     /// folder present in directory
-    pub(crate) fn list_cargo_locks(&self, path: &Path) -> Result<CargoLockFiles> {
+    pub(crate) fn list_cargo_locks(
+        &self,
+        path: &Path,
+        scan_cache: &ScanCache,
+    ) -> Result<CargoLockFiles> {
         let mut cargo_lock_files = CargoLockFiles::new();
+        for lock_file in self.list_files(path, "Cargo.lock", scan_cache)? {
+            cargo_lock_files.add_path(lock_file);
+        }
+        Ok(cargo_lock_files)
+    }
+
+    /// List Cargo.toml files by recursively analyze all folder present in
+    /// directory
+    pub(crate) fn list_cargo_manifests(
+        &self,
+        path: &Path,
+        scan_cache: &ScanCache,
+    ) -> Result<Vec<PathBuf>> {
+        self.list_files(path, "Cargo.toml", scan_cache)
+    }
+
+    /// List files with provided name by recursively analyze all folder which
+    /// are not ignored. Listing of unchanged directory is reused from scan
+    /// cache
+    fn list_files(&self, path: &Path, name: &str, scan_cache: &ScanCache) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
         // Use symlink_metadata so we don't follow symlinks when checking existence/type
         let Ok(sym_meta) = path.symlink_metadata() else {
-            return Ok(cargo_lock_files);
+            return Ok(files);
         };
         if sym_meta.is_symlink() {
-            return Ok(cargo_lock_files);
+            return Ok(files);
         }
         if !self.need_to_be_ignored(path)? {
             if sym_meta.is_dir() {
-                for entry in fs::read_dir(path)
-                    .context("failed to read directory while trying to find cargo.toml")?
-                {
-                    cargo_lock_files.append(self.list_cargo_locks(&entry?.path())?);
+                let dir_listing = scan_cache
+                    .dir_listing(path, Stamp::new(&sym_meta), || read_dir_listing(path))?;
+                for dir in &dir_listing.dirs {
+                    files.append(&mut self.list_files(&path.join(dir), name, scan_cache)?);
                 }
-            } else if sym_meta.is_file() && path.file_name() == Some(OsStr::new("Cargo.lock")) {
-                cargo_lock_files.add_path(path.to_path_buf());
+                for file in dir_listing.files.iter().filter(|file| *file == name) {
+                    let file_path = path.join(file);
+                    if !self.need_to_be_ignored(&file_path)? {
+                        files.push(file_path);
+                    }
+                }
+            } else if sym_meta.is_file() && path.file_name() == Some(OsStr::new(name)) {
+                files.push(path.to_path_buf());
             }
         }
-        Ok(cargo_lock_files)
+        Ok(files)
     }
 
     /// check if directory should be scanned for listing crates or not
@@ -227,6 +420,29 @@ impl ConfigFile {
     }
 }
 
+/// list sub directories which are not symlink and Cargo.lock and Cargo.toml
+/// files present in directory
+fn read_dir_listing(path: &Path) -> Result<DirListing> {
+    let mut dir_listing = DirListing::default();
+    for entry in
+        fs::read_dir(path).context("failed to read directory while trying to find cargo.toml")?
+    {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let Some(file_name) = entry.file_name().to_str().map(ToString::to_string) else {
+            continue;
+        };
+        if file_type.is_dir() {
+            dir_listing.dirs.push(file_name);
+        } else if file_type.is_file() && (file_name == "Cargo.lock" || file_name == "Cargo.toml") {
+            dir_listing.files.push(file_name);
+        }
+    }
+    dir_listing.dirs.sort();
+    dir_listing.files.sort();
+    Ok(dir_listing)
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::{Path, PathBuf};
@@ -350,6 +566,33 @@ mod tests {
         assert!(!cfg.need_to_be_ignored(Path::new("/a/.cache")).unwrap());
     }
 
+    #[test]
+    fn keep_override_applies_in_dry_run_test() {
+        let mut cfg = ConfigFile::default();
+        cfg.set_keep(Some(3), true, false).unwrap();
+        assert_eq!(cfg.keep(), 3);
+        cfg.set_keep(Some(5), true, true).unwrap();
+        assert_eq!(cfg.keep(), 3);
+    }
+
+    #[test]
+    fn semver_compatible_override_applies_in_dry_run_test() {
+        let mut cfg = ConfigFile::default();
+        cfg.set_semver_compatible(true, true, false).unwrap();
+        assert!(cfg.semver_compatible());
+        cfg.set_semver_compatible(false, true, true).unwrap();
+        assert!(cfg.semver_compatible());
+    }
+
+    #[test]
+    fn resolve_missing_locks_override_applies_in_dry_run_test() {
+        let mut cfg = ConfigFile::default();
+        cfg.set_resolve_missing_locks(true, true, false).unwrap();
+        assert!(cfg.resolve_missing_locks());
+        cfg.set_resolve_missing_locks(false, true, true).unwrap();
+        assert!(cfg.resolve_missing_locks());
+    }
+
     #[test]
     fn multiple_ignore_entries_test() {
         let cfg = config_with_ignore(&["node_modules", "crates/demo"]);
@@ -5,13 +5,21 @@ use std::fs;
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
 use std::str::FromStr as _;
+use std::time::SystemTime;
 
 use anyhow::{Context as _, Result};
 use semver::Version;
 use serde::Deserialize;
 use url::Url;
 
-use crate::utils::{get_size, split_name_version};
+use crate::cargo_config::{
+    CRATES_IO_INDEX, CRATES_IO_SPARSE_INDEX, SourceConfig, canonical_registry,
+};
+use crate::dir_size::DirSizes;
+use crate::global_cache::GlobalCache;
+use crate::scan_cache::ScanCache;
+use crate::source_hash::{git_dir_names, registry_dir_names};
+use crate::utils::{get_last_used, split_name_version};
 
 #[derive(Debug, Clone)]
 pub(crate) struct CrateMetaData {
@@ -20,6 +28,7 @@ pub(crate) struct CrateMetaData {
     size: u64,
     source: Option<String>,
     path: Option<PathBuf>,
+    last_used: Option<SystemTime>,
 }
 
 impl CrateMetaData {
@@ -30,6 +39,7 @@ impl CrateMetaData {
             size: 0,
             source,
             path: None,
+            last_used: None,
         }
     }
 
@@ -52,6 +62,18 @@ impl CrateMetaData {
     pub(crate) fn path(&self) -> Option<&PathBuf> {
         self.path.as_ref()
     }
+
+    pub(crate) fn last_used(&self) -> Option<SystemTime> {
+        self.last_used
+    }
+
+    /// set size and last used time of crate metadata for testing
+    #[cfg(test)]
+    pub(crate) fn with_usage(mut self, size: u64, last_used: Option<SystemTime>) -> Self {
+        self.size = size;
+        self.last_used = last_used;
+        self
+    }
 }
 
 impl PartialOrd for CrateMetaData {
@@ -100,17 +122,30 @@ struct IndexConfig {
 #[derive(Default)]
 pub(crate) struct CrateDetail {
     source_infos: HashMap<String, Url>,
+    source_config: SourceConfig,
     bin: HashSet<CrateMetaData>,
     git_crates_source: HashSet<CrateMetaData>,
     registry_crates_source: HashSet<CrateMetaData>,
     git_crates_archive: HashSet<CrateMetaData>,
     registry_crates_archive: HashSet<CrateMetaData>,
+    global_cache: GlobalCache,
+    dir_sizes: DirSizes,
 }
 
 impl CrateDetail {
-    /// Crate new index info
-    pub(crate) fn new(index_dir: &Path, db_dir: &Path) -> Result<Self> {
+    /// Crate new index info. Last use time recorded in cargo global cache
+    /// tracker database is used when database is present. Registry urls of
+    /// source config are used to find full url of sparse registry
+    pub(crate) fn new(
+        index_dir: &Path,
+        db_dir: &Path,
+        global_cache_file: &Path,
+        source_config: SourceConfig,
+    ) -> Result<Self> {
         let mut source_infos = HashMap::new();
+        // sparse crates.io index is not part of cargo config but its directory name
+        // can be computed same as configured registry
+        let crates_io_sparse = Url::from_str(CRATES_IO_SPARSE_INDEX)?;
         if index_dir.exists() && index_dir.is_dir() {
             for entry in fs::read_dir(index_dir)? {
                 let registry_dir = entry?.path();
@@ -120,6 +155,21 @@ impl CrateDetail {
                     .to_str()
                     .context("failed to convert OSstr to str")?;
 
+                // Use known registry url whose hashed directory name is same as directory
+                // name since it doesn't depend on content of index
+                if let Some(url) = source_config
+                    .registry_urls()
+                    .chain([&crates_io_sparse])
+                    .find(|url| {
+                        registry_dir_names(url)
+                            .iter()
+                            .any(|name| name == registry_file_name)
+                    })
+                {
+                    source_infos.insert(registry_file_name.to_string(), strip_sparse(url)?);
+                    continue;
+                }
+
                 // file for git based registry
                 let mut fetch_head_file = registry_dir.clone();
                 fetch_head_file.push(".git");
@@ -132,16 +182,9 @@ impl CrateDetail {
                 // Check if fetch head file exists if it exists than index is old registry based
                 // index instead of new sparse based
                 if fetch_head_file.exists() {
-                    let content = fs::read_to_string(fetch_head_file)
-                        .context("failed to read FETCH_HEAD file")?;
-                    let url_path = content
-                        .split_whitespace()
-                        .last()
-                        .context("failed to get url part from content")?;
-                    source_infos.insert(
-                        registry_file_name.to_string(),
-                        Url::from_str(url_path).context("fail FETCH_HEAD url conversion")?,
-                    );
+                    if let Some(url) = read_fetch_head_url(&fetch_head_file) {
+                        source_infos.insert(registry_file_name.to_string(), url);
+                    }
                 // Else if config file exists it is based on sparse registry
                 } else if config_file.exists() {
                     let domain = registry_file_name
@@ -175,26 +218,23 @@ impl CrateDetail {
                     .context("failed to convert osstr to str")?;
                 let mut fetch_head_file = git_dir.clone();
                 fetch_head_file.push("FETCH_HEAD");
-                let content = fs::read_to_string(fetch_head_file)
-                    .context("failed to read FETCH_HEAD file")?;
-                let url_path = content
-                    .split_whitespace()
-                    .last()
-                    .context("failed to get url part from content")?;
-                source_infos.insert(
-                    git_file_name.to_string(),
-                    Url::from_str(url_path).context("failed to convert db dir FETCH_HEAD")?,
-                );
+                // missing or corrupt FETCH_HEAD is matched using hashed directory name
+                if let Some(url) = read_fetch_head_url(&fetch_head_file) {
+                    source_infos.insert(git_file_name.to_string(), url);
+                }
             }
         }
+        let global_cache = GlobalCache::load(global_cache_file);
         Ok(Self {
             source_infos,
+            source_config,
+            global_cache,
             ..Default::default()
         })
     }
 
     /// Get index name from url
-    pub(crate) fn index_names_from_url(&self, url: &Url) -> Vec<String> {
+    fn index_names_from_url(&self, url: &Url) -> Vec<String> {
         self.source_infos
             .iter()
             .filter_map(
@@ -205,6 +245,51 @@ impl CrateDetail {
             .collect()
     }
 
+    /// Get index names of registry url recorded in Cargo.lock. Source
+    /// replacement of provided source config is followed and every local index
+    /// holding same registry is returned, so all spelling of crates.io map to
+    /// both git and sparse index. Directory names computed from url are
+    /// included so crates are matched even when index cannot be read
+    pub(crate) fn index_names_from_source(
+        &self,
+        url: &Url,
+        source_config: &SourceConfig,
+    ) -> Result<Vec<String>> {
+        let url = source_config.resolve(url);
+        let identity = canonical_registry(&url);
+        let mut index_names = self
+            .source_infos
+            .iter()
+            .filter(|(_, value)| canonical_registry(value) == identity)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        if identity == canonical_registry(&Url::from_str(CRATES_IO_INDEX)?) {
+            index_names.extend(registry_dir_names(&Url::from_str(CRATES_IO_INDEX)?));
+            index_names.extend(registry_dir_names(&Url::from_str(CRATES_IO_SPARSE_INDEX)?));
+        } else {
+            index_names.extend(registry_dir_names(&url));
+        }
+        index_names.sort();
+        index_names.dedup();
+        Ok(index_names)
+    }
+
+    /// Get db directory names of git url recorded in Cargo.lock. Directory
+    /// names computed from url are included so crates are matched even when
+    /// `FETCH_HEAD` is missing
+    pub(crate) fn git_names_from_url(&self, url: &Url) -> Vec<String> {
+        let mut git_names = self.index_names_from_url(url);
+        git_names.extend(git_dir_names(url));
+        git_names.sort();
+        git_names.dedup();
+        git_names
+    }
+
+    /// Get source config loaded from cargo config
+    pub(crate) fn source_config(&self) -> &SourceConfig {
+        &self.source_config
+    }
+
     /// Get source infos
     pub(crate) fn source_infos(&self) -> &HashMap<String, Url> {
         &self.source_infos
@@ -260,14 +345,53 @@ impl CrateDetail {
         self.registry_crates_archive.insert(crate_metadata.clone());
     }
 
+    /// compute size of cache directories in single parallel pass so sizes of
+    /// installed crates and query are read from recorded result. Size of
+    /// crate directories of immutable roots is reused from scan cache
+    pub(crate) fn scan_sizes(
+        &self,
+        roots: &[&Path],
+        immutable_roots: &[&Path],
+        scan_cache: &ScanCache,
+    ) {
+        self.dir_sizes.scan(roots, immutable_roots, scan_cache);
+    }
+
+    /// return size of path reusing sizes recorded by scan
+    pub(crate) fn dir_size(&self, path: &Path) -> Result<u64> {
+        self.dir_sizes.size(path)
+    }
+
+    /// return total size of paths reusing sizes recorded by scan. Hard linked
+    /// file shared by paths is counted only once
+    pub(crate) fn total_dir_size(&self, paths: &[&Path]) -> Result<u64> {
+        self.dir_sizes.total_size(paths)
+    }
+
+    /// check if last used time of crates is read from cargo global cache
+    /// tracker instead of file system
+    pub(crate) fn has_usage_tracker(&self) -> bool {
+        !self.global_cache.is_empty()
+    }
+
+    /// return last used time of entry recorded by scan before entry was read
+    fn entry_last_used(&self, path: &Path) -> Option<SystemTime> {
+        self.dir_sizes
+            .last_used(path)
+            .or_else(|| get_last_used(path))
+    }
+
     /// list installed bin
     pub(crate) fn list_installed_bin(&mut self, bin_dir: &Path) -> Result<Vec<CrateMetaData>> {
         let mut installed_bin = Vec::new();
         if bin_dir.exists() && bin_dir.is_dir() {
             for entry in fs::read_dir(bin_dir).context("failed to read bin directory")? {
                 let entry_path = entry?.path();
-                let bin_size =
-                    get_size(&entry_path).context("failed to get size of bin directory")?;
+                let last_used = self.entry_last_used(&entry_path);
+                let bin_size = self
+                    .dir_sizes
+                    .size(&entry_path)
+                    .context("failed to get size of bin directory")?;
                 let file_name = entry_path
                     .file_name()
                     .context("failed to get file name from bin directory")?;
@@ -281,6 +405,7 @@ impl CrateDetail {
                     size: bin_size,
                     source: None,
                     path: None,
+                    last_used,
                 };
                 self.add_bin(&bin_metadata);
                 installed_bin.push(bin_metadata);
@@ -306,23 +431,35 @@ impl CrateDetail {
                         fs::read_dir(&registry).context("failed to read registry folder")?
                     {
                         let dir_entry_path = dir_entry?.path();
-                        let crate_size = get_size(&dir_entry_path)
-                            .context("failed to get registry crate size")?;
                         let file_name = dir_entry_path
                             .file_name()
                             .context("failed to get file name from main entry")?;
                         let crate_name = file_name
                             .to_str()
                             .context("failed to convert crate file name to str")?;
+                        let source = registry
+                            .file_name()
+                            .and_then(|f| f.to_str())
+                            .map(ToString::to_string);
+                        // read last used time before size since reading directory for size
+                        // updates access time
+                        let last_used = source
+                            .as_ref()
+                            .and_then(|index_name| {
+                                self.global_cache.registry_src(index_name, crate_name)
+                            })
+                            .or_else(|| self.entry_last_used(&dir_entry_path));
+                        let crate_size = self
+                            .dir_sizes
+                            .size(&dir_entry_path)
+                            .context("failed to get registry crate size")?;
                         let (name, version) = split_name_version(crate_name)?;
                         let crate_metadata = CrateMetaData {
                             name,
                             version: Some(version),
                             size: crate_size,
-                            source: registry
-                                .file_name()
-                                .and_then(|f| f.to_str())
-                                .map(ToString::to_string),
+                            source,
+                            last_used,
                             path: Some(dir_entry_path),
                         };
                         self.add_registry_crate_source(&crate_metadata);
@@ -343,19 +480,30 @@ impl CrateDetail {
                         let file_name = dir_entry_path
                             .file_name()
                             .context("failed to get file name from cache dir")?;
-                        let crate_size = get_size(&dir_entry_path).context("failed to get size")?;
                         let crate_name = file_name
                             .to_str()
                             .context("failed to convert crate file name to str")?;
+                        let source = registry
+                            .file_name()
+                            .and_then(|f| f.to_str())
+                            .map(ToString::to_string);
+                        let last_used = source
+                            .as_ref()
+                            .and_then(|index_name| {
+                                self.global_cache.registry_crate(index_name, crate_name)
+                            })
+                            .or_else(|| self.entry_last_used(&dir_entry_path));
+                        let crate_size = self
+                            .dir_sizes
+                            .size(&dir_entry_path)
+                            .context("failed to get size")?;
                         let (name, version) = split_name_version(crate_name)?;
                         let crate_metadata = CrateMetaData {
                             name,
                             version: Some(version),
                             size: crate_size,
-                            source: registry
-                                .file_name()
-                                .and_then(|f| f.to_str())
-                                .map(ToString::to_string),
+                            source,
+                            last_used,
                             path: Some(dir_entry_path),
                         };
                         self.add_registry_crate_archive(&crate_metadata);
@@ -391,8 +539,6 @@ impl CrateDetail {
                         .context("failed to read checkout dir sub folder")?
                     {
                         let git_sha_entry_path = git_sha_entry?.path();
-                        let crate_size =
-                            get_size(&git_sha_entry_path).context("failed to get folder size")?;
                         let git_sha_file_name = git_sha_entry_path
                             .file_name()
                             .context("failed to get file name")?;
@@ -402,6 +548,14 @@ impl CrateDetail {
                         let file_name = file_path
                             .to_str()
                             .context("failed to convert file path file name to str")?;
+                        let last_used = self
+                            .global_cache
+                            .git_checkout(file_name, git_sha)
+                            .or_else(|| self.entry_last_used(&git_sha_entry_path));
+                        let crate_size = self
+                            .dir_sizes
+                            .size(&git_sha_entry_path)
+                            .context("failed to get folder size")?;
                         let crate_name_initial = file_name
                             .rsplit_once('-')
                             .context("failed to split git cache directory name")?
@@ -415,6 +569,7 @@ impl CrateDetail {
                                 .file_name()
                                 .and_then(|f| f.to_str())
                                 .map(ToString::to_string),
+                            last_used,
                             path: Some(git_sha_entry_path),
                         };
                         self.add_git_crate_archive(&crate_metadata);
@@ -430,12 +585,18 @@ impl CrateDetail {
                 if !entry_path.is_dir() {
                     continue;
                 }
-                let crate_size =
-                    get_size(&entry_path).context("failed to get size of db dir folders")?;
                 let file_name = entry_path.file_name().context("failed to get file name")?;
                 let file_name_str = file_name
                     .to_str()
                     .context("failed to convert db dir file name to str")?;
+                let last_used = self
+                    .global_cache
+                    .git_db(file_name_str)
+                    .or_else(|| self.entry_last_used(&entry_path));
+                let crate_size = self
+                    .dir_sizes
+                    .size(&entry_path)
+                    .context("failed to get size of db dir folders")?;
                 let crate_name_initial = file_name_str
                     .rsplit_once('-')
                     .context("failed to split db dir entry name")?
@@ -449,6 +610,7 @@ impl CrateDetail {
                         .file_name()
                         .and_then(|f| f.to_str())
                         .map(ToString::to_string),
+                    last_used,
                     path: Some(entry_path),
                 };
                 self.add_git_crate_source(&crate_metadata);
@@ -466,17 +628,36 @@ impl CrateDetail {
 
 fn update_crate_list(hash_set: &mut HashSet<CrateMetaData>, temp_crate_metadata: &CrateMetaData) {
     let mut crate_metadata = temp_crate_metadata.clone();
-    // accumulate size of an existing equal entry before reinserting
+    // accumulate size and keep latest use time of an existing equal entry before
+    // reinserting
     if let Some(existing) = hash_set.take(temp_crate_metadata) {
         crate_metadata.size += existing.size;
+        crate_metadata.last_used = crate_metadata.last_used.max(existing.last_used);
     }
     hash_set.insert(crate_metadata);
 }
 
+/// read url of remote from `FETCH_HEAD` file. Return none when file is
+/// missing or corrupt
+fn read_fetch_head_url(fetch_head_file: &Path) -> Option<Url> {
+    let content = fs::read_to_string(fetch_head_file).ok()?;
+    let url_path = content.split_whitespace().last()?;
+    Url::from_str(url_path).ok()
+}
+
+/// remove `sparse+` prefix of sparse registry url
+fn strip_sparse(url: &Url) -> Result<Url> {
+    match url.as_str().strip_prefix("sparse+") {
+        Some(sparse_url) => Url::from_str(sparse_url).context("failed sparse registry url"),
+        None => Ok(url.clone()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::cmp::Ordering;
     use std::collections::HashSet;
+    use std::time::{Duration, SystemTime};
 
     use semver::Version;
 
@@ -489,6 +670,7 @@ mod tests {
             size,
             source: Some(source.to_string()),
             path: None,
+            last_used: None,
         }
     }
 
@@ -520,6 +702,20 @@ mod tests {
         assert_eq!(set.iter().next().unwrap().size(), 150);
     }
 
+    #[test]
+    fn update_crate_list_keeps_latest_use_time_test() {
+        let older = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let newer = SystemTime::UNIX_EPOCH + Duration::from_secs(200);
+        let mut set = HashSet::new();
+        let mut first = meta("serde", "1.0.0", "registry", 100);
+        first.last_used = Some(newer);
+        let mut second = meta("serde", "1.0.0", "registry", 50);
+        second.last_used = Some(older);
+        update_crate_list(&mut set, &first);
+        update_crate_list(&mut set, &second);
+        assert_eq!(set.iter().next().unwrap().last_used(), Some(newer));
+    }
+
     #[test]
     fn update_crate_list_keeps_distinct_entries_test() {
         let mut set = HashSet::new();
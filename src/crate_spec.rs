@@ -0,0 +1,167 @@
+use anyhow::{Context as _, Result};
+use semver::{Version, VersionReq};
+
+use crate::crate_detail::CrateMetaData;
+use crate::utils::split_name_version;
+
+/// Version part of crate spec. Plain version only matches exact version
+/// while other value is parsed as version requirement such as `^1`
+enum VersionSpec {
+    Exact(Version),
+    Req(VersionReq),
+}
+
+impl VersionSpec {
+    fn parse(value: &str) -> Result<Self> {
+        if let Ok(version) = Version::parse(value) {
+            return Ok(Self::Exact(version));
+        }
+        let req = VersionReq::parse(value)
+            .with_context(|| format!("failed to parse version requirement {value}"))?;
+        Ok(Self::Req(req))
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        match self {
+            Self::Exact(exact) => exact == version,
+            Self::Req(req) => req.matches(version),
+        }
+    }
+}
+
+/// Crate provided by user as `name`, `name@version`, `name@^1` or cache
+/// directory name such as `serde-1.0.188`. Name can also be checkout name of
+/// git crate and can contain `*` and `?` wildcard such as `windows-*`
+pub(crate) struct CrateSpec {
+    name: String,
+    version: Option<VersionSpec>,
+}
+
+impl CrateSpec {
+    /// parse crate spec. Value without `@` is only split into name and version
+    /// when it ends with valid semver version
+    pub(crate) fn parse(value: &str) -> Result<Self> {
+        if let Some((name, version)) = value.split_once('@') {
+            let version = VersionSpec::parse(version)
+                .with_context(|| format!("failed to parse version of {value}"))?;
+            return Ok(Self {
+                name: name.to_string(),
+                version: Some(version),
+            });
+        }
+        match split_name_version(value) {
+            Ok((name, version)) if !name.is_empty() && !is_wildcard(&name) => {
+                Ok(Self {
+                    name,
+                    version: Some(VersionSpec::Exact(version)),
+                })
+            }
+            _ => {
+                Ok(Self {
+                    name: value.to_string(),
+                    version: None,
+                })
+            }
+        }
+    }
+
+    /// check if name and version matches with spec
+    pub(crate) fn matches_parts(&self, name: &str, version: Option<&Version>) -> bool {
+        let version_matches = match &self.version {
+            Some(version_spec) => version.is_some_and(|version| version_spec.matches(version)),
+            None => true,
+        };
+        version_matches && wildcard_match(&self.name, name)
+    }
+
+    /// check if crate matches with spec
+    pub(crate) fn matches(&self, crate_metadata: &CrateMetaData) -> bool {
+        self.matches_parts(crate_metadata.name(), crate_metadata.version())
+    }
+}
+
+/// check if value contains wildcard character
+fn is_wildcard(value: &str) -> bool {
+    value.contains(['*', '?'])
+}
+
+/// match value against pattern where `*` matches any number of characters and
+/// `?` matches single character. Pattern without wildcard needs exact match
+pub(crate) fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let value = value.chars().collect::<Vec<_>>();
+    let (mut pattern_pos, mut value_pos) = (0, 0);
+    // position of last `*` in pattern and value position it started matching
+    let mut backtrack = None;
+    while value_pos < value.len() {
+        match pattern.get(pattern_pos) {
+            Some('*') => {
+                backtrack = Some((pattern_pos, value_pos));
+                pattern_pos += 1;
+            }
+            Some(&character) if character == '?' || character == value[value_pos] => {
+                pattern_pos += 1;
+                value_pos += 1;
+            }
+            _ => {
+                let Some((star_pos, star_value_pos)) = backtrack else {
+                    return false;
+                };
+                pattern_pos = star_pos + 1;
+                value_pos = star_value_pos + 1;
+                backtrack = Some((star_pos, value_pos));
+            }
+        }
+    }
+    pattern[pattern_pos..]
+        .iter()
+        .all(|&character| character == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::{CrateSpec, wildcard_match};
+
+    #[test]
+    fn parse_crate_spec_test() {
+        let serde = Version::new(1, 0, 188);
+        for value in ["serde@1.0.188", "serde-1.0.188"] {
+            let spec = CrateSpec::parse(value).unwrap();
+            assert!(spec.matches_parts("serde", Some(&serde)));
+            assert!(!spec.matches_parts("serde", Some(&Version::new(1, 0, 189))));
+        }
+        let spec = CrateSpec::parse("serde-json").unwrap();
+        assert!(spec.matches_parts("serde-json", Some(&serde)));
+        assert!(spec.matches_parts("serde-json", None));
+        let spec = CrateSpec::parse("cargo-1a2b3c4").unwrap();
+        assert!(spec.matches_parts("cargo-1a2b3c4", None));
+        assert!(CrateSpec::parse("serde@latest").is_err());
+    }
+
+    #[test]
+    fn version_requirement_spec_test() {
+        let spec = CrateSpec::parse("serde@^1").unwrap();
+        assert!(spec.matches_parts("serde", Some(&Version::new(1, 0, 188))));
+        assert!(!spec.matches_parts("serde", Some(&Version::new(2, 0, 0))));
+        assert!(!spec.matches_parts("serde", None));
+        // plain version is exact instead of caret requirement
+        let spec = CrateSpec::parse("serde@1.0.100").unwrap();
+        assert!(!spec.matches_parts("serde", Some(&Version::new(1, 0, 188))));
+    }
+
+    #[test]
+    fn wildcard_spec_test() {
+        let spec = CrateSpec::parse("windows-*").unwrap();
+        assert!(spec.matches_parts("windows-sys", Some(&Version::new(0, 52, 0))));
+        assert!(spec.matches_parts("windows-", None));
+        assert!(!spec.matches_parts("windows", None));
+        let spec = CrateSpec::parse("windows_*_msvc@0.52.*").unwrap();
+        assert!(spec.matches_parts("windows_x86_64_msvc", Some(&Version::new(0, 52, 6))));
+        assert!(!spec.matches_parts("windows_x86_64_gnu", Some(&Version::new(0, 52, 6))));
+        assert!(wildcard_match("a*b*c", "aXXbYYbc"));
+        assert!(wildcard_match("?erde", "serde"));
+        assert!(!wildcard_match("a*b", "acbd"));
+    }
+}
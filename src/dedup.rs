@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result};
+use rayon::prelude::*;
+
+use crate::dir_size::metadata_size;
+use crate::utils::sha256_file;
+
+/// File found while searching duplicates
+struct DedupFile {
+    path: PathBuf,
+    len: u64,
+    /// device and inode of file. Files sharing it are already hard linked
+    identity: Option<(u64, u64)>,
+    /// permission of file since hard linked files share permission
+    mode: u32,
+    /// number of hard links of file
+    links: u64,
+    /// size freed when all links of file are removed
+    size: u64,
+}
+
+/// Summary of hard link deduplication
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct DedupSummary {
+    /// number of files replaced by hard link
+    pub(crate) linked: usize,
+    /// size reclaimed by replacing files
+    pub(crate) reclaimed: u64,
+}
+
+/// find byte identical files in provided directories and replace duplicate
+/// with hard link of single file. Candidate files of same length, device and
+/// permission are compared by SHA-256 hash of their content. Hidden entries
+/// such as `.cargo-ok` and `.git` are skipped since cargo and git can rewrite
+/// them in place which would modify every linked copy. Linked files share
+/// content so directory whose files are edited in place such as git checkout
+/// should only be passed when user accepts that edit is visible in every copy
+pub(crate) fn dedup_files(roots: &[&Path], dry_run: bool) -> Result<DedupSummary> {
+    let files = roots.par_iter().map(|root| list_files(root)).try_reduce(
+        Vec::new,
+        |mut first, mut second| {
+            first.append(&mut second);
+            Ok(first)
+        },
+    )?;
+
+    // group files which can be identical and hard linked with each other
+    let mut candidates: HashMap<_, Vec<DedupFile>> = HashMap::new();
+    for file in files.into_iter().filter(|file| file.len > 0) {
+        let device = file.identity.map(|(device, _)| device);
+        candidates
+            .entry((file.len, device, file.mode))
+            .or_default()
+            .push(file);
+    }
+    let candidates = candidates
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(group_by_identity)
+        .filter(|group| group.len() > 1)
+        .flatten()
+        .collect::<Vec<_>>();
+
+    // hash only one path of already linked files
+    let hashed = candidates
+        .into_par_iter()
+        .map(|linked_files| {
+            let digest = sha256_file(&linked_files[0].path)?;
+            Ok((digest, linked_files))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut duplicates: HashMap<_, Vec<Vec<DedupFile>>> = HashMap::new();
+    for (digest, linked_files) in hashed {
+        duplicates.entry(digest).or_default().push(linked_files);
+    }
+
+    let mut summary = DedupSummary::default();
+    for mut group in duplicates.into_values().filter(|group| group.len() > 1) {
+        // keep file which already has most links so fewest paths are replaced
+        group.sort_by(|a, b| b[0].links.cmp(&a[0].links).then(a[0].path.cmp(&b[0].path)));
+        let Some((target, duplicates)) = group.split_first() else {
+            continue;
+        };
+        for linked_files in duplicates {
+            for file in linked_files {
+                if !dry_run {
+                    replace_with_link(&target[0].path, &file.path)?;
+                }
+                summary.linked += 1;
+            }
+            // content is freed only when every link of file is replaced
+            let links = u64::try_from(linked_files.len()).unwrap_or(u64::MAX);
+            if linked_files[0].links <= links {
+                summary.reclaimed += linked_files[0].size;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// split files into groups of files which are already hard linked with each
+/// other
+fn group_by_identity(files: Vec<DedupFile>) -> Vec<Vec<DedupFile>> {
+    let mut groups: Vec<Vec<DedupFile>> = Vec::new();
+    for file in files {
+        match groups
+            .iter_mut()
+            .find(|group| file.identity.is_some() && group[0].identity == file.identity)
+        {
+            Some(group) => group.push(file),
+            None => groups.push(vec![file]),
+        }
+    }
+    groups
+}
+
+/// list regular files present in directory without following symlink and
+/// skipping hidden entries
+fn list_files(path: &Path) -> Result<Vec<DedupFile>> {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return Ok(Vec::new());
+    };
+    if metadata.is_dir() {
+        let entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries
+            .par_iter()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .is_some_and(|file_name| !file_name.to_string_lossy().starts_with('.'))
+            })
+            .map(|entry| list_files(entry))
+            .try_reduce(Vec::new, |mut first, mut second| {
+                first.append(&mut second);
+                Ok(first)
+            })
+    } else if metadata.is_file() {
+        #[cfg(unix)]
+        let (identity, mode, links) = (
+            Some((metadata.dev(), metadata.ino())),
+            metadata.mode(),
+            metadata.nlink(),
+        );
+        #[cfg(not(unix))]
+        let (identity, mode, links) = (None, u32::from(metadata.permissions().readonly()), 1);
+        Ok(vec![DedupFile {
+            path: path.to_path_buf(),
+            len: metadata.len(),
+            identity,
+            mode,
+            links,
+            size: metadata_size(&metadata),
+        }])
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// replace file with hard link of target. Link is created with temporary name
+/// and renamed over file so file is never missing
+fn replace_with_link(target: &Path, path: &Path) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .context("failed to get file name of duplicate file")?;
+    let temp_path =
+        path.with_file_name(format!(".{}.cargo-trim-dedup", file_name.to_string_lossy()));
+    let _ = fs::remove_file(&temp_path);
+    fs::hard_link(target, &temp_path)
+        .with_context(|| format!("failed to link {}", path.display()))?;
+    if let Err(err) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err).with_context(|| format!("failed to replace {}", path.display()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::MetadataExt as _;
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use super::dedup_files;
+
+    #[cfg(unix)]
+    #[test]
+    fn dedup_files_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let src = root.join("src");
+        let checkouts = root.join("checkouts");
+        for dir in [
+            src.join("index.crates.io-1949cf8c6b5b557f/serde-1.0.0/src"),
+            src.join("mirror-0000000000000000/serde-1.0.0/src"),
+            checkouts.join("serde-0000000000000000/abcdef0/src"),
+        ] {
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("lib.rs"), "pub fn serde() {}").unwrap();
+            fs::write(dir.parent().unwrap().join(".cargo-ok"), "ok").unwrap();
+        }
+        let mirror_lib = src.join("mirror-0000000000000000/serde-1.0.0/src/lib.rs");
+        let checkout_lib = checkouts.join("serde-0000000000000000/abcdef0/src/lib.rs");
+        // same length with different content is not linked
+        fs::write(&checkout_lib, "pub fn other() {}").unwrap();
+        let inode = |path: &PathBuf| fs::metadata(path).unwrap().ino();
+
+        let summary = dedup_files(&[&src, &checkouts], true).unwrap();
+        assert_eq!(summary.linked, 1);
+        assert_ne!(
+            inode(&mirror_lib),
+            inode(&src.join("index.crates.io-1949cf8c6b5b557f/serde-1.0.0/src/lib.rs"))
+        );
+
+        assert_eq!(dedup_files(&[&src, &checkouts], false).unwrap(), summary);
+        assert_eq!(
+            inode(&mirror_lib),
+            inode(&src.join("index.crates.io-1949cf8c6b5b557f/serde-1.0.0/src/lib.rs"))
+        );
+        assert_eq!(
+            fs::read_to_string(&mirror_lib).unwrap(),
+            "pub fn serde() {}"
+        );
+        assert_ne!(inode(&checkout_lib), inode(&mirror_lib));
+        // hidden bookkeeping files are not linked
+        assert_eq!(
+            fs::metadata(src.join("mirror-0000000000000000/serde-1.0.0/.cargo-ok"))
+                .unwrap()
+                .nlink(),
+            1
+        );
+        // already linked files are not linked again
+        assert_eq!(dedup_files(&[&src, &checkouts], false).unwrap().linked, 0);
+    }
+}
@@ -15,7 +15,13 @@ fn get_cargo_home() -> Result<String> {
 /// Struct for storing Directory path
 pub(crate) struct DirPath {
     bin_dir: PathBuf,
+    crates_toml_file: PathBuf,
+    crates2_json_file: PathBuf,
     config_file: PathBuf,
+    scan_cache_file: PathBuf,
+    cargo_config_files: Vec<PathBuf>,
+    global_cache_file: PathBuf,
+    package_cache_lock_files: Vec<PathBuf>,
     git_dir: PathBuf,
     checkout_dir: PathBuf,
     db_dir: PathBuf,
@@ -23,6 +29,7 @@ pub(crate) struct DirPath {
     cache_dir: PathBuf,
     index_dir: PathBuf,
     src_dir: PathBuf,
+    trash_dir: PathBuf,
 }
 
 impl DirPath {
@@ -41,11 +48,31 @@ impl DirPath {
             fs::File::create(&config_file).context("failed to create config file")?;
         }
 
+        // set scan cache file path which is stored next to config file
+        let scan_cache_file = config_dir.join("cargo_trim_scan_cache.json");
+
         let home_dir = Path::new(&get_cargo_home()?).to_path_buf();
 
+        // set cargo config files path. Legacy config file without extension is
+        // read before config.toml so values of config.toml take precedence
+        let cargo_config_files = vec![home_dir.join("config"), home_dir.join("config.toml")];
+
+        // set cargo global cache tracker database path
+        let global_cache_file = home_dir.join(".global-cache");
+
+        // set cargo package cache lock files path in order they are locked by cargo
+        let package_cache_lock_files = vec![
+            home_dir.join(".package-cache"),
+            home_dir.join(".package-cache-mutate"),
+        ];
+
         // set bin directory path
         let bin_dir = home_dir.join("bin");
 
+        // set cargo install metadata files path
+        let crates_toml_file = home_dir.join(".crates.toml");
+        let crates2_json_file = home_dir.join(".crates2.json");
+
         // set git directory path
         let git_dir = home_dir.join("git");
 
@@ -61,9 +88,18 @@ impl DirPath {
         let src_dir = registry_dir.join("src");
         let index_dir = registry_dir.join("index");
 
+        // set trash dir path where removed files are moved in trash mode
+        let trash_dir = home_dir.join(".trim-trash");
+
         Ok(Self {
             bin_dir,
+            crates_toml_file,
+            crates2_json_file,
             config_file,
+            scan_cache_file,
+            cargo_config_files,
+            global_cache_file,
+            package_cache_lock_files,
             git_dir,
             checkout_dir,
             db_dir,
@@ -71,6 +107,7 @@ impl DirPath {
             cache_dir,
             index_dir,
             src_dir,
+            trash_dir,
         })
     }
 
@@ -79,11 +116,41 @@ impl DirPath {
         &self.bin_dir
     }
 
+    /// return path of cargo install v1 metadata file
+    pub(crate) fn crates_toml_file(&self) -> &PathBuf {
+        &self.crates_toml_file
+    }
+
+    /// return path of cargo install v2 metadata file
+    pub(crate) fn crates2_json_file(&self) -> &PathBuf {
+        &self.crates2_json_file
+    }
+
     /// return path of config file
     pub(crate) fn config_file(&self) -> &PathBuf {
         &self.config_file
     }
 
+    /// return path of scan cache file
+    pub(crate) fn scan_cache_file(&self) -> &PathBuf {
+        &self.scan_cache_file
+    }
+
+    /// return path of cargo config files present in cargo home
+    pub(crate) fn cargo_config_files(&self) -> &[PathBuf] {
+        &self.cargo_config_files
+    }
+
+    /// return path of cargo global cache tracker database
+    pub(crate) fn global_cache_file(&self) -> &PathBuf {
+        &self.global_cache_file
+    }
+
+    /// return path of cargo package cache lock files
+    pub(crate) fn package_cache_lock_files(&self) -> &[PathBuf] {
+        &self.package_cache_lock_files
+    }
+
     /// return path of git dir
     pub(crate) fn git_dir(&self) -> &PathBuf {
         &self.git_dir
@@ -118,4 +185,9 @@ impl DirPath {
     pub(crate) fn src_dir(&self) -> &PathBuf {
         &self.src_dir
     }
+
+    /// return path of trash dir
+    pub(crate) fn trash_dir(&self) -> &PathBuf {
+        &self.trash_dir
+    }
 }
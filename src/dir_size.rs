@@ -0,0 +1,440 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+use anyhow::Result;
+use rayon::prelude::*;
+
+use crate::scan_cache::{ScanCache, Stamp};
+use crate::utils::last_used_from_metadata;
+
+/// depth below scanned root till which size of entry is recorded. Depth three
+/// covers crate directory such as `registry/src/<index>/<crate>` and checkout
+/// such as `git/checkouts/<repo>/<rev>`
+const RECORD_DEPTH: usize = 3;
+
+/// depth below immutable root of crate directory such as
+/// `registry/src/<index>/<crate>` or checkout such as
+/// `git/checkouts/<repo>/<rev>`
+const CRATE_DEPTH: usize = 2;
+
+/// marker file written by cargo once crate directory or checkout is fully
+/// extracted. Content of directory is not modified after it is written
+const CARGO_OK_FILE: &str = ".cargo-ok";
+
+/// whether size is allocated disk usage instead of apparent length
+static DISK_SIZE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// set whether size is computed from allocated blocks. In disk mode file
+/// sharing same inode is counted only once
+pub(crate) fn set_disk_size_mode(value: bool) {
+    DISK_SIZE_MODE.store(value, Ordering::Relaxed);
+}
+
+fn disk_size_mode() -> bool {
+    DISK_SIZE_MODE.load(Ordering::Relaxed)
+}
+
+/// Size and last used time of entry recorded while walking
+#[derive(Clone, Copy)]
+struct EntryInfo {
+    size: u64,
+    last_used: Option<SystemTime>,
+}
+
+/// Size of entries computed by single parallel walk of cache directory. Later
+/// size lookup of scanned entry is answered from recorded sizes instead of
+/// walking same tree again
+#[derive(Default)]
+pub(crate) struct DirSizes {
+    entries: Mutex<HashMap<PathBuf, EntryInfo>>,
+    /// hard linked inodes already counted by scan in disk mode
+    inodes: Mutex<HashSet<u64>>,
+}
+
+impl DirSizes {
+    /// walk directories in parallel and record size of every entry till
+    /// record depth. Apparent size of extracted crate directory of immutable
+    /// root is reused from scan cache while stamp of crate directory is
+    /// unchanged. Disk usage is not cached since it changes when file of crate
+    /// is hard linked by dedup without changing stamp of crate directory
+    pub(crate) fn scan(&self, roots: &[&Path], immutable_roots: &[&Path], scan_cache: &ScanCache) {
+        let disk_mode = disk_size_mode();
+        let walker = Walker {
+            dir_sizes: Some(self),
+            scan_cache: None,
+            disk_mode,
+            inodes: &self.inodes,
+        };
+        let cached_walker = Walker {
+            scan_cache: (!disk_mode).then_some(scan_cache),
+            ..walker
+        };
+        roots
+            .par_iter()
+            .map(|root| (root, &walker))
+            .chain(
+                immutable_roots
+                    .par_iter()
+                    .map(|root| (root, &cached_walker)),
+            )
+            .for_each(|(root, walker)| {
+                // failure is ignored since size is computed again on lookup
+                let _ = walker.walk(root, 0);
+            });
+    }
+
+    /// return size of path. Recorded size is reused and only part of tree
+    /// which was not scanned before is walked
+    pub(crate) fn size(&self, path: &Path) -> Result<u64> {
+        Walker {
+            dir_sizes: Some(self),
+            scan_cache: None,
+            disk_mode: disk_size_mode(),
+            inodes: &Mutex::default(),
+        }
+        .walk(path, 0)
+    }
+
+    /// return total size of paths. Recorded size is reused and walk of part
+    /// which was not scanned before shares single inode set so hard linked
+    /// file is counted only once across all paths in disk mode
+    pub(crate) fn total_size(&self, paths: &[&Path]) -> Result<u64> {
+        let inodes = Mutex::default();
+        let walker = Walker {
+            dir_sizes: Some(self),
+            scan_cache: None,
+            disk_mode: disk_size_mode(),
+            inodes: &inodes,
+        };
+        paths.iter().map(|path| walker.walk(path, 0)).sum()
+    }
+
+    /// return last used time of path recorded before it was walked
+    pub(crate) fn last_used(&self, path: &Path) -> Option<SystemTime> {
+        self.lock()
+            .get(path)
+            .and_then(|entry_info| entry_info.last_used)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<PathBuf, EntryInfo>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// compute size of path by walking directory entries in parallel. Rayon
+/// distributes entries of each directory over work stealing thread pool
+pub(crate) fn walk_size(path: &Path) -> Result<u64> {
+    Walker {
+        dir_sizes: None,
+        scan_cache: None,
+        disk_mode: disk_size_mode(),
+        inodes: &Mutex::default(),
+    }
+    .walk(path, 0)
+}
+
+/// compute size of path where file sharing same inode is counted only once
+pub(crate) fn walk_inode_handled_size(path: &Path, inodes: &mut HashSet<u64>) -> Result<u64> {
+    let mut total_size = 0;
+    for (inode, size) in walk_files(path, disk_size_mode())? {
+        if inode.is_none_or(|inode| inodes.insert(inode)) {
+            total_size += size;
+        }
+    }
+    Ok(total_size)
+}
+
+/// return size of file according to size mode. Apparent size is length of
+/// file and disk size is number of allocated 512 byte blocks
+fn file_size(metadata: &fs::Metadata, disk_mode: bool) -> u64 {
+    #[cfg(unix)]
+    if disk_mode {
+        return metadata.blocks() * 512;
+    }
+    metadata.len()
+}
+
+/// return size of file according to current size mode
+pub(crate) fn metadata_size(metadata: &fs::Metadata) -> u64 {
+    file_size(metadata, disk_size_mode())
+}
+
+/// State shared by recursive walk of single size computation
+#[derive(Clone, Copy)]
+struct Walker<'a> {
+    /// record of entry sizes till record depth
+    dir_sizes: Option<&'a DirSizes>,
+    /// cache of apparent size of extracted crate directories
+    scan_cache: Option<&'a ScanCache>,
+    /// whether disk usage is computed instead of apparent size
+    disk_mode: bool,
+    /// hard linked inodes already counted in disk mode
+    inodes: &'a Mutex<HashSet<u64>>,
+}
+
+impl Walker<'_> {
+    /// recursively compute size of path. Size of entries till record depth is
+    /// stored in dir sizes and already recorded entry is not walked again
+    fn walk(&self, path: &Path, depth: usize) -> Result<u64> {
+        if let Some(entry_info) = self
+            .dir_sizes
+            .and_then(|dir_sizes| dir_sizes.lock().get(path).copied())
+        {
+            return Ok(entry_info.size);
+        }
+        let Ok(metadata) = path.metadata() else {
+            return Ok(0);
+        };
+        let size = if metadata.is_dir() {
+            match self.scan_cache {
+                Some(scan_cache) if depth == CRATE_DEPTH && path.join(CARGO_OK_FILE).is_file() => {
+                    scan_cache
+                        .entry_size(path, Stamp::new(&metadata), || self.walk_dir(path, depth))?
+                }
+                _ => self.walk_dir(path, depth)?,
+            }
+        } else if metadata.is_file() {
+            if self.is_counted_link(&metadata) {
+                0
+            } else {
+                file_size(&metadata, self.disk_mode)
+            }
+        } else {
+            0
+        };
+        if let Some(dir_sizes) = self.dir_sizes
+            && depth <= RECORD_DEPTH
+        {
+            let entry_info = EntryInfo {
+                size,
+                last_used: last_used_from_metadata(&metadata),
+            };
+            dir_sizes.lock().insert(path.to_path_buf(), entry_info);
+        }
+        Ok(size)
+    }
+
+    /// compute total size of entries of directory in parallel
+    fn walk_dir(&self, path: &Path, depth: usize) -> Result<u64> {
+        let entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries
+            .par_iter()
+            .map(|entry| self.walk(entry, depth + 1))
+            .try_reduce(|| 0, |first, second| Ok(first + second))
+    }
+
+    /// check if hard linked file was already counted in disk mode
+    #[cfg(unix)]
+    fn is_counted_link(&self, metadata: &fs::Metadata) -> bool {
+        self.disk_mode
+            && metadata.nlink() > 1
+            && !self
+                .inodes
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(metadata.ino())
+    }
+
+    #[cfg(not(unix))]
+    fn is_counted_link(&self, _metadata: &fs::Metadata) -> bool {
+        false
+    }
+}
+
+/// list inode and size of all files present in path
+fn walk_files(path: &Path, disk_mode: bool) -> Result<Vec<(Option<u64>, u64)>> {
+    let Ok(metadata) = path.metadata() else {
+        return Ok(Vec::new());
+    };
+    if metadata.is_dir() {
+        let entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries
+            .par_iter()
+            .map(|entry| walk_files(entry, disk_mode))
+            .try_reduce(Vec::new, |mut first, mut second| {
+                first.append(&mut second);
+                Ok(first)
+            })
+    } else if metadata.is_file() {
+        #[cfg(unix)]
+        let inode = Some(metadata.ino());
+        #[cfg(not(unix))]
+        let inode = None;
+        Ok(vec![(inode, file_size(&metadata, disk_mode))])
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::{DirSizes, Walker, walk_inode_handled_size, walk_size};
+    use crate::scan_cache::ScanCache;
+
+    #[test]
+    fn dir_sizes_reuse_scan_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let crate_dir = root.join("src/index/serde-1.0.0");
+        fs::create_dir_all(crate_dir.join("src")).unwrap();
+        fs::write(crate_dir.join("Cargo.toml"), vec![0; 100]).unwrap();
+        fs::write(crate_dir.join("src/lib.rs"), vec![0; 50]).unwrap();
+        fs::write(root.join("src/index/file"), vec![0; 10]).unwrap();
+
+        let dir_sizes = DirSizes::default();
+        dir_sizes.scan(
+            &[root],
+            &[],
+            &ScanCache::load(&root.join("scan_cache.json"), false),
+        );
+        assert_eq!(dir_sizes.size(root).unwrap(), 160);
+        assert_eq!(dir_sizes.size(&crate_dir).unwrap(), 150);
+        // removing file doesn't change recorded size of scanned entry
+        fs::remove_file(crate_dir.join("Cargo.toml")).unwrap();
+        assert_eq!(dir_sizes.size(&crate_dir).unwrap(), 150);
+        // entry deeper than record depth is walked again
+        assert_eq!(dir_sizes.size(&crate_dir.join("src")).unwrap(), 50);
+        assert_eq!(walk_size(&crate_dir).unwrap(), 50);
+    }
+
+    #[test]
+    fn dir_sizes_parent_reuse_scan_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("index")).unwrap();
+        fs::write(root.join("src/lib.rs"), vec![0; 100]).unwrap();
+        fs::write(root.join("index/config.json"), vec![0; 10]).unwrap();
+
+        let dir_sizes = DirSizes::default();
+        dir_sizes.scan(
+            &[&root.join("src")],
+            &[],
+            &ScanCache::load(&root.join("scan_cache.json"), false),
+        );
+        // scanned src is not walked again while computing size of parent
+        fs::write(root.join("src/main.rs"), vec![0; 30]).unwrap();
+        assert_eq!(dir_sizes.size(root).unwrap(), 110);
+        assert_eq!(walk_size(root).unwrap(), 140);
+    }
+
+    #[test]
+    fn dir_sizes_scan_cache_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let src_dir = root.join("src");
+        let crate_dir = src_dir.join("index/serde-1.0.0");
+        let partial_dir = src_dir.join("index/rand-0.8.0");
+        fs::create_dir_all(&crate_dir).unwrap();
+        fs::create_dir_all(&partial_dir).unwrap();
+        fs::write(crate_dir.join(".cargo-ok"), "").unwrap();
+        fs::write(crate_dir.join("lib.rs"), vec![0; 100]).unwrap();
+        fs::write(partial_dir.join("lib.rs"), vec![0; 50]).unwrap();
+        let cache_file = root.join("scan_cache.json");
+
+        let scan = || {
+            let scan_cache = ScanCache::load(&cache_file, true);
+            let dir_sizes = DirSizes::default();
+            dir_sizes.scan(&[], &[&src_dir], &scan_cache);
+            scan_cache.save().unwrap();
+            (
+                dir_sizes.size(&crate_dir).unwrap(),
+                dir_sizes.size(&partial_dir).unwrap(),
+            )
+        };
+        assert_eq!(scan(), (100, 50));
+        // file modified in place keeps stamp of crate directory so cached size
+        // is reused only for directory which has cargo ok marker
+        fs::write(crate_dir.join("lib.rs"), vec![0; 200]).unwrap();
+        fs::write(partial_dir.join("lib.rs"), vec![0; 80]).unwrap();
+        assert_eq!(scan(), (100, 80));
+        // new entry changes stamp of crate directory
+        fs::write(crate_dir.join("main.rs"), vec![0; 10]).unwrap();
+        assert_eq!(scan(), (210, 80));
+    }
+
+    #[test]
+    fn dir_sizes_total_size_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for (dir, len) in [("cache", 100), ("src", 50), ("index", 10)] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("file"), vec![0; len]).unwrap();
+        }
+
+        let dir_sizes = DirSizes::default();
+        dir_sizes.scan(
+            &[&root.join("cache")],
+            &[],
+            &ScanCache::load(&root.join("scan_cache.json"), false),
+        );
+        fs::write(root.join("cache/new"), vec![0; 30]).unwrap();
+        // scanned cache is reused and missing directory has no size
+        assert_eq!(
+            dir_sizes
+                .total_size(&[&root.join("cache"), &root.join("src"), &root.join("db")])
+                .unwrap(),
+            150
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn inode_handled_size_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("first"), vec![0; 100]).unwrap();
+        fs::hard_link(root.join("first"), root.join("second")).unwrap();
+        fs::write(root.join("third"), vec![0; 20]).unwrap();
+        assert_eq!(walk_size(root).unwrap(), 220);
+        assert_eq!(
+            walk_inode_handled_size(root, &mut HashSet::new()).unwrap(),
+            120
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn disk_size_test() {
+        use std::os::unix::fs::MetadataExt as _;
+        use std::sync::Mutex;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("small"), vec![1; 10]).unwrap();
+        fs::hard_link(root.join("small"), root.join("linked")).unwrap();
+        // sparse file doesn't allocate block for hole
+        fs::File::create(root.join("sparse"))
+            .unwrap()
+            .set_len(1 << 20)
+            .unwrap();
+        let blocks = |name: &str| fs::metadata(root.join(name)).unwrap().blocks() * 512;
+        let walk = |disk_mode| {
+            Walker {
+                dir_sizes: None,
+                scan_cache: None,
+                disk_mode,
+                inodes: &Mutex::default(),
+            }
+            .walk(root, 0)
+            .unwrap()
+        };
+        assert_eq!(walk(false), 10 + 10 + (1 << 20));
+        assert_eq!(walk(true), blocks("small") + blocks("sparse"));
+    }
+}
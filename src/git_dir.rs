@@ -2,7 +2,7 @@ use anyhow::{Context as _, Result};
 use owo_colors::OwoColorize as _;
 
 use crate::crate_detail::{CrateDetail, CrateMetaData};
-use crate::utils::delete_folder;
+use crate::utils::{delete_folder, print_message};
 
 /// Store git dir folder information
 pub(crate) struct GitDir;
@@ -40,7 +40,7 @@ impl GitDir {
             true
         };
         if dry_run {
-            println!(
+            print_message(format_args!(
                 "{} {} {} {}",
                 "Dry run:".yellow(),
                 "Removed".red(),
@@ -50,10 +50,10 @@ impl GitDir {
                     .map(ToString::to_string)
                     .unwrap_or_default(),
                 crate_metadata.name(),
-            );
+            ));
             Ok(true)
         } else if is_success {
-            println!(
+            print_message(format_args!(
                 "{} {} {}",
                 "Removed".red(),
                 crate_metadata
@@ -62,10 +62,10 @@ impl GitDir {
                     .map(ToString::to_string)
                     .unwrap_or_default(),
                 crate_metadata.name()
-            );
+            ));
             Ok(true)
         } else {
-            println!(
+            print_message(format_args!(
                 "Failed to remove {} {}",
                 crate_metadata
                     .source()
@@ -73,25 +73,23 @@ impl GitDir {
                     .map(ToString::to_string)
                     .unwrap_or_default(),
                 crate_metadata.name(),
-            );
+            ));
             Ok(false)
         }
     }
 
-    /// Remove list of crates
+    /// Remove list of crates and return list of crates which were removed
     pub(crate) fn remove_crate_list(
         crate_detail: &CrateDetail,
         list: &[CrateMetaData],
         dry_run: bool,
-    ) -> Result<(u64, usize)> {
-        let mut size_cleaned = 0;
-        let mut crate_removed = 0;
+    ) -> Result<Vec<CrateMetaData>> {
+        let mut removed_crates = Vec::new();
         for crate_metadata in list {
             if Self::remove_crate(crate_detail, crate_metadata, dry_run)? {
-                size_cleaned += crate_metadata.size();
-                crate_removed += 1;
+                removed_crates.push(crate_metadata.clone());
             }
         }
-        Ok((size_cleaned, crate_removed))
+        Ok(removed_crates)
     }
 }
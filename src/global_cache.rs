@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context as _, Result};
+use owo_colors::OwoColorize as _;
+use rusqlite::{Connection, OpenFlags};
+
+use crate::utils::print_message;
+
+/// Stores last use time of cache entries recorded by cargo global cache
+/// tracker database
+#[derive(Default)]
+pub(crate) struct GlobalCache {
+    registry_crate: HashMap<(String, String), SystemTime>,
+    registry_src: HashMap<(String, String), SystemTime>,
+    git_db: HashMap<String, SystemTime>,
+    git_checkout: HashMap<(String, String), SystemTime>,
+}
+
+impl GlobalCache {
+    /// Load global cache tracker database. Return empty global cache if
+    /// database file doesn't exist or cannot be read such as when it is locked
+    /// by running cargo or its schema is changed by newer cargo, so last use
+    /// time falls back to file system time
+    pub(crate) fn load(path: &Path) -> Self {
+        if !path.is_file() {
+            return Self::default();
+        }
+        match Self::read(path) {
+            Ok(global_cache) => global_cache,
+            Err(err) => {
+                print_message(format_args!(
+                    "{} cargo global cache tracker {}: {}",
+                    "Skipped".yellow(),
+                    path.display(),
+                    err.root_cause()
+                ));
+                Self::default()
+            }
+        }
+    }
+
+    /// read last use time of every table of global cache tracker database
+    fn read(path: &Path) -> Result<Self> {
+        // open in read only mode so cargo which owns database is never affected
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .context("failed to open global cache database")?;
+        let registry_crate = read_pair_timestamps(
+            &connection,
+            "SELECT registry_index.name, registry_crate.name, registry_crate.timestamp FROM \
+             registry_crate JOIN registry_index ON registry_crate.registry_id = registry_index.id",
+        )
+        .context("failed to read registry_crate table of global cache database")?;
+        let registry_src = read_pair_timestamps(
+            &connection,
+            "SELECT registry_index.name, registry_src.name, registry_src.timestamp FROM \
+             registry_src JOIN registry_index ON registry_src.registry_id = registry_index.id",
+        )
+        .context("failed to read registry_src table of global cache database")?;
+        let git_checkout = read_pair_timestamps(
+            &connection,
+            "SELECT git_db.name, git_checkout.name, git_checkout.timestamp FROM git_checkout JOIN \
+             git_db ON git_checkout.git_id = git_db.id",
+        )
+        .context("failed to read git_checkout table of global cache database")?;
+        let mut git_db = HashMap::new();
+        let mut statement = connection
+            .prepare("SELECT name, timestamp FROM git_db")
+            .context("failed to read git_db table of global cache database")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (name, timestamp) = row?;
+            git_db.insert(name, timestamp_to_system_time(timestamp));
+        }
+        Ok(Self {
+            registry_crate,
+            registry_src,
+            git_db,
+            git_checkout,
+        })
+    }
+
+    /// check if no last use time is recorded
+    pub(crate) fn is_empty(&self) -> bool {
+        self.registry_crate.is_empty()
+            && self.registry_src.is_empty()
+            && self.git_db.is_empty()
+            && self.git_checkout.is_empty()
+    }
+
+    /// return last use time of registry .crate archive file
+    pub(crate) fn registry_crate(&self, index_name: &str, file_name: &str) -> Option<SystemTime> {
+        self.registry_crate
+            .get(&(index_name.to_string(), file_name.to_string()))
+            .copied()
+    }
+
+    /// return last use time of extracted registry src directory
+    pub(crate) fn registry_src(&self, index_name: &str, dir_name: &str) -> Option<SystemTime> {
+        self.registry_src
+            .get(&(index_name.to_string(), dir_name.to_string()))
+            .copied()
+    }
+
+    /// return last use time of git db directory
+    pub(crate) fn git_db(&self, db_name: &str) -> Option<SystemTime> {
+        self.git_db.get(db_name).copied()
+    }
+
+    /// return last use time of git checkout directory
+    pub(crate) fn git_checkout(&self, db_name: &str, rev: &str) -> Option<SystemTime> {
+        self.git_checkout
+            .get(&(db_name.to_string(), rev.to_string()))
+            .copied()
+    }
+}
+
+/// run query which returns parent name, entry name and timestamp columns
+fn read_pair_timestamps(
+    connection: &Connection,
+    query: &str,
+) -> Result<HashMap<(String, String), SystemTime>> {
+    let mut pair_timestamps = HashMap::new();
+    let mut statement = connection.prepare(query)?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+        ))
+    })?;
+    for row in rows {
+        let (parent_name, name, timestamp) = row?;
+        pair_timestamps.insert((parent_name, name), timestamp_to_system_time(timestamp));
+    }
+    Ok(pair_timestamps)
+}
+
+/// convert unix timestamp in seconds stored by cargo to system time
+fn timestamp_to_system_time(timestamp: i64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(u64::try_from(timestamp).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    use rusqlite::Connection;
+    use tempfile::TempDir;
+
+    use super::GlobalCache;
+
+    fn create_fixture(path: &Path) {
+        let connection = Connection::open(path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE registry_index (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT \
+                 UNIQUE NOT NULL, timestamp INTEGER NOT NULL);
+                 CREATE TABLE registry_crate (registry_id INTEGER NOT NULL, name TEXT NOT NULL, \
+                 size INTEGER NOT NULL, timestamp INTEGER NOT NULL, PRIMARY KEY (registry_id, \
+                 name));
+                 CREATE TABLE registry_src (registry_id INTEGER NOT NULL, name TEXT NOT NULL, size \
+                 INTEGER, timestamp INTEGER NOT NULL, PRIMARY KEY (registry_id, name));
+                 CREATE TABLE git_db (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT UNIQUE NOT \
+                 NULL, timestamp INTEGER NOT NULL);
+                 CREATE TABLE git_checkout (git_id INTEGER NOT NULL, name TEXT NOT NULL, size \
+                 INTEGER, timestamp INTEGER NOT NULL, PRIMARY KEY (git_id, name));
+                 INSERT INTO registry_index VALUES (1, 'index.crates.io-1949cf8c6b5b557f', 10);
+                 INSERT INTO registry_crate VALUES (1, 'serde-1.0.0.crate', 100, 1001);
+                 INSERT INTO registry_src VALUES (1, 'serde-1.0.0', 200, 2002);
+                 INSERT INTO git_db VALUES (1, 'bar-0123456789abcdef', 3003);
+                 INSERT INTO git_checkout VALUES (1, 'abcdef1', 300, 4004);",
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn load_global_cache_fixture_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(".global-cache");
+        create_fixture(&path);
+        let global_cache = GlobalCache::load(&path);
+        let index_name = "index.crates.io-1949cf8c6b5b557f";
+        assert_eq!(
+            global_cache.registry_crate(index_name, "serde-1.0.0.crate"),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1001))
+        );
+        assert_eq!(
+            global_cache.registry_src(index_name, "serde-1.0.0"),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(2002))
+        );
+        assert_eq!(
+            global_cache.git_db("bar-0123456789abcdef"),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(3003))
+        );
+        assert_eq!(
+            global_cache.git_checkout("bar-0123456789abcdef", "abcdef1"),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(4004))
+        );
+        assert_eq!(global_cache.registry_src(index_name, "serde-2.0.0"), None);
+    }
+
+    #[test]
+    fn load_missing_global_cache_is_empty_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let global_cache = GlobalCache::load(&temp_dir.path().join(".global-cache"));
+        assert!(global_cache.is_empty());
+    }
+
+    #[test]
+    fn load_unknown_schema_global_cache_is_empty_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(".global-cache");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE git_db (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT UNIQUE NOT \
+                 NULL, timestamp INTEGER NOT NULL);
+                 INSERT INTO git_db VALUES (1, 'bar-0123456789abcdef', 3003);",
+            )
+            .unwrap();
+        let global_cache = GlobalCache::load(&path);
+        assert!(global_cache.is_empty());
+    }
+}
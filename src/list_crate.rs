@@ -1,15 +1,22 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr as _;
+use std::time::{Duration, SystemTime};
 
 use anyhow::{Context as _, Result};
 use semver::Version;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::config_file::ConfigFile;
 use crate::crate_detail::{CrateDetail, CrateMetaData};
+use crate::crate_spec::CrateSpec;
 use crate::dir_path::DirPath;
+use crate::missing_lock::ResolvedLock;
+use crate::project::{Project, detect_projects};
+use crate::protect::ProtectList;
+use crate::scan_cache::{ScanCache, Stamp};
 
 /// struct to store Cargo.lock location
 pub(crate) struct CargoLockFiles {
@@ -34,38 +41,220 @@ impl CargoLockFiles {
     }
 }
 
-#[derive(Clone, Deserialize)]
-struct LockData {
-    package: Option<Vec<Package>>,
+/// Raw content of Cargo.lock file shared by all format versions
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct RawLockFile {
+    #[serde(default)]
+    package: Vec<RawPackage>,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
 }
 
-impl LockData {
-    fn package(&self) -> Option<&Vec<Package>> {
-        self.package.as_ref()
+#[derive(Clone, Serialize, Deserialize)]
+struct RawPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    checksum: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+/// Git source recorded in Cargo.lock
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct GitSource {
+    url: Url,
+    precise: String,
+}
+
+impl GitSource {
+    /// return short form of locked commit which is used as checkout directory
+    /// name by cargo
+    fn short_id(&self) -> &str {
+        &self.precise[..7]
+    }
+
+    /// return name of checkout directory created by cargo for locked commit
+    fn checkout_name(&self) -> Result<String> {
+        let last_path_segment = self
+            .url
+            .path_segments()
+            .context("url doesn't have segment")?
+            .next_back()
+            .context("cannot get last segments of path")?;
+        Ok(format!("{last_path_segment}-{}", self.short_id()))
     }
 }
 
-#[derive(Clone, Deserialize)]
-struct Package {
+/// Source of package recorded in Cargo.lock
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum LockSource {
+    /// registry url without `registry+` prefix
+    Registry(Url),
+    /// sparse registry url along with `sparse+` prefix
+    Sparse(Url),
+    Git(GitSource),
+}
+
+impl LockSource {
+    /// parse source of package. Return none for source kind which is not
+    /// cached by cargo such as path or local registry
+    fn parse(source: &str) -> Result<Option<Self>> {
+        if let Some(registry) = source.strip_prefix("registry+") {
+            let url =
+                Url::from_str(registry).context("failed registry source url kind conversion")?;
+            Ok(Some(Self::Registry(url)))
+        } else if source.starts_with("sparse+") {
+            let url = Url::from_str(source).context("failed sparse source url kind conversion")?;
+            Ok(Some(Self::Sparse(url)))
+        } else if source.starts_with("git+") {
+            Ok(Some(Self::Git(parse_git_source(source)?)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Package recorded in Cargo.lock
+struct LockPackage {
     name: String,
-    version: String,
-    source: Option<String>,
+    version: Version,
+    source: Option<LockSource>,
+    checksum: Option<String>,
+    /// dependencies as `name`, `name version` or `name version (source)`
+    dependencies: Vec<String>,
 }
 
-impl Package {
-    fn name(&self) -> &str {
-        &self.name
+impl LockPackage {
+    /// check if package matches with spec. Git package also matches with its
+    /// checkout name
+    fn matches(&self, spec: &CrateSpec) -> bool {
+        spec.matches_parts(&self.name, Some(&self.version))
+            || matches!(&self.source, Some(LockSource::Git(git_source))
+                if git_source
+                    .checkout_name()
+                    .is_ok_and(|checkout_name| spec.matches_parts(&checkout_name, None)))
     }
+}
+
+/// Cargo.lock file of any format version. Version 1 stores checksum in
+/// `[metadata]` table, version 2 and later store it in package and version 4
+/// percent encodes git reference
+struct LockFile {
+    packages: Vec<LockPackage>,
+}
 
-    fn version(&self) -> &str {
-        &self.version
+impl LockFile {
+    /// parse content of Cargo.lock file
+    fn parse(content: &str) -> Result<Self> {
+        Self::from_raw(parse_raw_lock_file(content)?)
     }
 
-    fn source(&self) -> Option<&String> {
-        self.source.as_ref()
+    /// convert raw content of Cargo.lock file. Checksum is read from package
+    /// or from `[metadata]` table so every format version is handled same way
+    fn from_raw(raw_lock_file: RawLockFile) -> Result<Self> {
+        let mut packages = Vec::new();
+        for package in raw_lock_file.package {
+            let checksum = package
+                .checksum
+                .or_else(|| {
+                    let source = package.source.as_ref()?;
+                    raw_lock_file
+                        .metadata
+                        .get(&format!(
+                            "checksum {} {} ({source})",
+                            package.name, package.version
+                        ))
+                        .cloned()
+                })
+                .filter(|checksum| checksum != "<none>");
+            let source = match &package.source {
+                Some(source) => LockSource::parse(source)?,
+                None => None,
+            };
+            packages.push(LockPackage {
+                version: Version::parse(&package.version)
+                    .context("failed Cargo.lock semver version parse")?,
+                name: package.name,
+                source,
+                checksum,
+                dependencies: package.dependencies,
+            });
+        }
+        Ok(Self { packages })
+    }
+
+    /// find index of package referenced by dependency entry of other package
+    fn dependency_index(&self, dependency: &str) -> Option<usize> {
+        let mut parts = dependency.split_whitespace();
+        let name = parts.next()?;
+        let version = parts
+            .next()
+            .and_then(|version| Version::parse(version).ok());
+        self.packages.iter().position(|package| {
+            package.name == name
+                && version
+                    .as_ref()
+                    .is_none_or(|version| package.version == *version)
+        })
+    }
+
+    /// find shortest dependency path from workspace member to every package
+    /// matched by spec. Packages without source are workspace members or path
+    /// dependencies so search starts from them
+    fn dependency_paths(&self, spec: &CrateSpec) -> Vec<Vec<String>> {
+        let mut parent = vec![None; self.packages.len()];
+        let mut visited = vec![false; self.packages.len()];
+        let mut queue = VecDeque::new();
+        for (index, package) in self.packages.iter().enumerate() {
+            if package.source.is_none() {
+                visited[index] = true;
+                queue.push_back(index);
+            }
+        }
+        while let Some(index) = queue.pop_front() {
+            for dependency in &self.packages[index].dependencies {
+                if let Some(dependency_index) = self.dependency_index(dependency)
+                    && !visited[dependency_index]
+                {
+                    visited[dependency_index] = true;
+                    parent[dependency_index] = Some(index);
+                    queue.push_back(dependency_index);
+                }
+            }
+        }
+        self.packages
+            .iter()
+            .enumerate()
+            .filter(|(_, package)| package.matches(spec))
+            .map(|(index, _)| {
+                let mut path = vec![index];
+                while let Some(parent_index) = parent[*path.last().unwrap_or(&index)] {
+                    path.push(parent_index);
+                }
+                path.iter()
+                    .rev()
+                    .map(|&index| {
+                        let package = &self.packages[index];
+                        format!("{} v{}", package.name, package.version)
+                    })
+                    .collect()
+            })
+            .collect()
     }
 }
 
+/// find dependency paths from workspace members to packages matched by spec
+/// in Cargo.lock file
+pub(crate) fn lock_file_dependency_paths(
+    cargo_lock_file: &Path,
+    spec: &CrateSpec,
+) -> Result<Vec<Vec<String>>> {
+    let file_content = fs::read_to_string(cargo_lock_file)
+        .context("failed to read cargo lock content to string")?;
+    Ok(LockFile::parse(&file_content)?.dependency_paths(spec))
+}
+
 /// struct to store all crate list detail with its type
 pub(crate) struct CrateList {
     installed_bin: Vec<CrateMetaData>,
@@ -76,6 +265,22 @@ pub(crate) struct CrateList {
     orphan_crate_registry: Vec<CrateMetaData>,
     orphan_crate_git: Vec<CrateMetaData>,
     cargo_lock_files: CargoLockFiles,
+    projects: Vec<Project>,
+    lock_checksums: LockChecksums,
+    protect_list: ProtectList,
+}
+
+/// Checksums of registry crate recorded in scanned Cargo.lock files along with
+/// first Cargo.lock which recorded each checksum
+type LockChecksums = HashMap<CrateMetaData, Vec<(String, PathBuf)>>;
+
+/// crates used by scanned projects
+struct UsedCrates {
+    cargo_lock_files: CargoLockFiles,
+    projects: Vec<Project>,
+    lock_checksums: LockChecksums,
+    registry: Vec<CrateMetaData>,
+    git: Vec<CrateMetaData>,
 }
 
 impl CrateList {
@@ -84,6 +289,8 @@ impl CrateList {
         dir_path: &DirPath,
         config_file: &ConfigFile,
         crate_detail: &mut CrateDetail,
+        resolved_locks: &[ResolvedLock],
+        scan_cache: &ScanCache,
     ) -> Result<Self> {
         let bin_dir = dir_path.bin_dir();
         let cache_dir = dir_path.cache_dir();
@@ -91,6 +298,13 @@ impl CrateList {
         let checkout_dir = dir_path.checkout_dir();
         let db_dir = dir_path.db_dir();
 
+        // compute size of all cache directories in single parallel pass
+        crate_detail.scan_sizes(
+            &[bin_dir, cache_dir, db_dir],
+            &[src_dir, checkout_dir],
+            scan_cache,
+        );
+
         // list installed crates
         let installed_bin = crate_detail.list_installed_bin(bin_dir)?;
         let installed_crate_registry =
@@ -102,11 +316,21 @@ impl CrateList {
             db_dir,
             installed_crate_registry.clone(),
             &installed_crate_git,
+            config_file.keep(),
+            config_file.semver_compatible(),
         )?;
 
         // list all used crates in rust program
-        let (cargo_lock_files, used_crate_registry, used_crate_git) =
-            list_used_crates(config_file, crate_detail)?;
+        let UsedCrates {
+            cargo_lock_files,
+            mut projects,
+            lock_checksums,
+            registry: used_crate_registry,
+            git: used_crate_git,
+        } = list_used_crates(config_file, crate_detail, resolved_locks, scan_cache)?;
+        for project in &mut projects {
+            project.compute_pinned_size(&installed_crate_registry, &installed_crate_git);
+        }
 
         // list orphan crates. If crate is not used then it is orphan
         let (orphan_crate_registry, orphan_crate_git) = list_orphan_crates(
@@ -125,6 +349,9 @@ impl CrateList {
             orphan_crate_registry,
             orphan_crate_git,
             cargo_lock_files,
+            projects,
+            lock_checksums,
+            protect_list: ProtectList::new(config_file.protect())?,
         })
     }
 
@@ -168,6 +395,84 @@ impl CrateList {
         &self.cargo_lock_files
     }
 
+    /// return checksums of registry crate recorded in scanned Cargo.lock files
+    pub(crate) fn lock_checksums(&self, crate_metadata: &CrateMetaData) -> &[(String, PathBuf)] {
+        self.lock_checksums
+            .get(crate_metadata)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// provide list of detected projects
+    pub(crate) fn projects(&self) -> &[Project] {
+        &self.projects
+    }
+
+    /// check if crate is protected by protect list of config file
+    pub(crate) fn is_protected(&self, crate_metadata: &CrateMetaData) -> bool {
+        self.protect_list.is_protected(crate_metadata)
+    }
+
+    /// remove protected crates from list of crates. Every cleaning command
+    /// filters crates with it before removing them
+    pub(crate) fn unprotected(&self, crates: &[CrateMetaData]) -> Vec<CrateMetaData> {
+        crates
+            .iter()
+            .filter(|crate_metadata| !self.is_protected(crate_metadata))
+            .cloned()
+            .collect()
+    }
+
+    /// find project whose workspace root or member is provided directory
+    pub(crate) fn find_project(&self, dir: &Path) -> Option<&Project> {
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        self.projects
+            .iter()
+            .find(|project| project.contains_dir(&dir))
+    }
+
+    /// list installed registry and git crates referenced by project
+    pub(crate) fn used_by(&self, project: &Project) -> (Vec<CrateMetaData>, Vec<CrateMetaData>) {
+        let registry = self
+            .installed_crate_registry
+            .iter()
+            .filter(|crate_metadata| project.references_registry(crate_metadata))
+            .cloned()
+            .collect();
+        let git = self
+            .installed_crate_git
+            .iter()
+            .filter(|crate_metadata| project.references_git(crate_metadata))
+            .cloned()
+            .collect();
+        (registry, git)
+    }
+
+    /// list installed registry and git crates referenced by project and not
+    /// referenced by any other project. These crates become orphan when project
+    /// is removed
+    pub(crate) fn exclusive_to(
+        &self,
+        project: &Project,
+    ) -> (Vec<CrateMetaData>, Vec<CrateMetaData>) {
+        let others = self
+            .projects
+            .iter()
+            .filter(|other| other.root() != project.root())
+            .collect::<Vec<_>>();
+        let (mut registry, mut git) = self.used_by(project);
+        registry.retain(|crate_metadata| {
+            !others
+                .iter()
+                .any(|other| other.references_registry(crate_metadata))
+        });
+        git.retain(|crate_metadata| {
+            !others
+                .iter()
+                .any(|other| other.references_git(crate_metadata))
+        });
+        (registry, git)
+    }
+
     /// list crates which is both old and orphan
     pub(crate) fn old_orphan_registry(&self) -> Vec<CrateMetaData> {
         let mut old_orphan_registry = Vec::new();
@@ -191,148 +496,166 @@ impl CrateList {
         }
         old_orphan_git
     }
+
+    /// list registry and git crates which need to be removed to bring cache
+    /// size under max size
+    pub(crate) fn over_budget_crates(
+        &self,
+        current_size: u64,
+        max_size: u64,
+    ) -> (Vec<CrateMetaData>, Vec<CrateMetaData>) {
+        // protected crates still occupy space but are never selected
+        list_over_budget_crates(
+            &self.unprotected(self.installed_registry()),
+            &self.unprotected(self.installed_git()),
+            self.orphan_registry(),
+            self.orphan_git(),
+            current_size,
+            max_size,
+        )
+    }
+
+    /// list registry crates which are not used for provided duration
+    pub(crate) fn unused_registry(&self, duration: Duration) -> Vec<CrateMetaData> {
+        list_unused_crates(self.installed_registry(), duration)
+    }
+
+    /// list git crates which are not used for provided duration
+    pub(crate) fn unused_git(&self, duration: Duration) -> Vec<CrateMetaData> {
+        list_unused_crates(self.installed_git(), duration)
+    }
 }
 
-/// Parse a `git+…` source string from Cargo.lock and return `(repo_url,
-/// short_sha)`.
-fn parse_git_source(source: &str) -> Result<(Url, String)> {
-    let (url_with_kind, sha_part) =
-        if source.contains("?rev=") || source.contains("?branch=") || source.contains("?tag=") {
-            let (base, query_and_hash) = if source.contains("?rev=") {
-                source
-                    .split_once("?rev=")
-                    .context("failed to split ?rev= from git source")?
-            } else if source.contains("?branch=") {
-                source
-                    .split_once("?branch=")
-                    .context("failed to split ?branch= from git source")?
-            } else {
-                source
-                    .split_once("?tag=")
-                    .context("failed to split ?tag= from git source")?
-            };
-            let sha_part = query_and_hash
-                .split_once('#')
-                .context("failed to find # in git source query param")?
-                .1;
-            (base, sha_part)
-        } else {
-            let (base, hash) = source
-                .split_once('#')
-                .context("failed to find # in git source")?;
-            (base, hash)
-        };
-    let rev_short_form = sha_part
-        .get(..7)
-        .context("git SHA in Cargo.lock is shorter than 7 characters")?
-        .to_string();
-    let url = Url::from_str(&url_with_kind.replace("git+", ""))
+/// Parse a `git+…` source string from Cargo.lock. Reference query such as
+/// percent encoded branch of Cargo.lock version 4 is removed since checkout is
+/// identified by url and locked commit
+fn parse_git_source(source: &str) -> Result<GitSource> {
+    let mut url = Url::from_str(source.trim_start_matches("git+"))
         .context("failed git source url kind with query params conversion")?;
-    Ok((url, rev_short_form))
+    let precise = url
+        .fragment()
+        .context("failed to find # in git source")?
+        .to_string();
+    if precise.len() < 7 || !precise.is_char_boundary(7) {
+        anyhow::bail!("git SHA in Cargo.lock is shorter than 7 characters");
+    }
+    url.set_query(None);
+    url.set_fragment(None);
+    Ok(GitSource { url, precise })
 }
 
-/// Read out content of Cargo.lock file to List crates present so can be
-/// used for orphan clean
-fn read_content(
-    cargo_lock_paths: &[PathBuf],
+/// parse content of Cargo.lock file without interpreting it
+fn parse_raw_lock_file(content: &str) -> Result<RawLockFile> {
+    toml::from_str(content).context("failed to convert to toml format")
+}
+
+/// Crates present in single Cargo.lock file
+struct LockFileCrates {
+    registry: Vec<CrateMetaData>,
+    git: Vec<CrateMetaData>,
+    /// checksum of registry crates recorded in Cargo.lock
+    checksums: Vec<(CrateMetaData, String)>,
+}
+
+/// Read crates present in Cargo.lock file of project directory. Project
+/// directory is used for finding cargo config since Cargo.lock can be
+/// generated outside of project. Parsed content is reused from scan cache when
+/// it is provided and file is unchanged
+fn read_lock_file(
+    cargo_lock_file: &Path,
+    project_dir: &Path,
     crate_detail: &CrateDetail,
-) -> Result<(Vec<CrateMetaData>, Vec<CrateMetaData>)> {
+    scan_cache: Option<&ScanCache>,
+) -> Result<LockFileCrates> {
     let mut present_crate_registry = Vec::new();
     let mut present_crate_git = Vec::new();
-    for cargo_lock_file in cargo_lock_paths {
-        if cargo_lock_file.exists() {
-            let file_content = fs::read_to_string(cargo_lock_file)
-                .context("failed to read cargo lock content to string")?;
-            let cargo_lock_data: LockData =
-                toml::from_str(&file_content).context("failed to convert to toml format")?;
-            if let Some(packages) = cargo_lock_data.package() {
-                for package in packages {
-                    if let Some(source) = package.source() {
-                        let name = package.name();
-                        let version = package.version();
-                        if source.contains("registry+") {
-                            let mut url = Url::from_str(&source.replace("registry+", ""))
-                                .context("failed registry source url kind conversion")?;
-                            // Only add sparse registry if sparse registry is present in place of
-                            // git based registry for crates.io
-                            let index_crates_url = Url::from_str("https://index.crates.io")?;
-                            if url == Url::from_str("https://github.com/rust-lang/crates.io-index")?
-                                && crate_detail
-                                    .source_infos()
-                                    .values()
-                                    .collect::<Vec<_>>()
-                                    .contains(&&index_crates_url)
-                            {
-                                url = index_crates_url;
-                            }
-                            for index_name in crate_detail.index_names_from_url(&url) {
-                                present_crate_registry.push(CrateMetaData::new(
-                                    name.to_string(),
-                                    Some(Version::parse(version).context(
-                                        "failed Cargo.lock semver
-                            version parse",
-                                    )?),
-                                    Some(index_name),
-                                ));
-                            }
-                        }
-                        if source.contains("git+") {
-                            let (url, rev_short_form) = parse_git_source(source)?;
-                            let last_path_segment = url
-                                .path_segments()
-                                .context("url doesn't have segment")?
-                                .next_back()
-                                .context("cannot get last segments of path")?;
-                            let full_name = format!("{last_path_segment}-{rev_short_form}");
-                            for index_name in crate_detail.index_names_from_url(&url) {
-                                present_crate_git.push(CrateMetaData::new(
-                                    full_name.clone(),
-                                    None,
-                                    Some(index_name),
-                                ));
-                            }
-                        }
-                        if source.contains("sparse+") {
-                            let url = Url::from_str(&source.replace("sparse+", ""))
-                                .context("failed sparse source url kind conversion")?;
-                            for index_name in crate_detail.index_names_from_url(&url) {
-                                present_crate_registry.push(CrateMetaData::new(
-                                    name.to_string(),
-                                    Some(
-                                        Version::parse(version)
-                                            .context("failed Cargo.lock semver version parse")?,
-                                    ),
-                                    Some(index_name),
-                                ));
-                            }
-                        }
+    let mut checksums = Vec::new();
+    let read_raw_lock_file = || {
+        let file_content = fs::read_to_string(cargo_lock_file)
+            .context("failed to read cargo lock content to string")?;
+        parse_raw_lock_file(&file_content)
+    };
+    let raw_lock_file = match scan_cache {
+        Some(scan_cache) => {
+            let metadata = cargo_lock_file
+                .metadata()
+                .context("failed to read cargo lock metadata")?;
+            scan_cache.lock_file(cargo_lock_file, Stamp::new(&metadata), read_raw_lock_file)?
+        }
+        None => read_raw_lock_file()?,
+    };
+    let lock_file = LockFile::from_raw(raw_lock_file)?;
+    // project cargo config can replace source used by project
+    let source_config = crate_detail.source_config().for_project(project_dir);
+    for package in lock_file.packages {
+        match &package.source {
+            Some(LockSource::Registry(url) | LockSource::Sparse(url)) => {
+                for index_name in crate_detail.index_names_from_source(url, &source_config)? {
+                    let crate_metadata = CrateMetaData::new(
+                        package.name.clone(),
+                        Some(package.version.clone()),
+                        Some(index_name),
+                    );
+                    if let Some(checksum) = &package.checksum {
+                        checksums.push((crate_metadata.clone(), checksum.clone()));
                     }
+                    present_crate_registry.push(crate_metadata);
+                }
+            }
+            Some(LockSource::Git(git_source)) => {
+                let full_name = git_source.checkout_name()?;
+                for index_name in crate_detail.git_names_from_url(&git_source.url) {
+                    present_crate_git.push(CrateMetaData::new(
+                        full_name.clone(),
+                        None,
+                        Some(index_name),
+                    ));
                 }
             }
+            None => {}
         }
     }
-    Ok((present_crate_registry, present_crate_git))
+    Ok(LockFileCrates {
+        registry: present_crate_registry,
+        git: present_crate_git,
+        checksums,
+    })
 }
 
-/// List old crates
+/// List old crates. For registry crates all versions except `keep` newest
+/// versions of each crate from same source are old. When semver compatible is
+/// true versions are only compared against semver compatible versions
 fn list_old_crates(
     db_dir: &Path,
     installed_crate_registry: Vec<CrateMetaData>,
     installed_crate_git: &[CrateMetaData],
+    keep: usize,
+    semver_compatible: bool,
 ) -> Result<(Vec<CrateMetaData>, Vec<CrateMetaData>)> {
     let mut old_crate_registry = Vec::new();
     let mut registry_crates = installed_crate_registry;
-    registry_crates.sort();
-    if registry_crates.len() > 1 {
-        for i in 0..(registry_crates.len() - 1) {
-            let crate_metadata = &registry_crates[i];
-            let next_crate_metadata = &registry_crates[i + 1];
-            if crate_metadata.name() == next_crate_metadata.name()
-                && crate_metadata.source() == next_crate_metadata.source()
-            {
-                old_crate_registry.push(crate_metadata.clone());
-            }
+    let compatible_line = |crate_metadata: &CrateMetaData| {
+        if semver_compatible {
+            crate_metadata.version().map(semver_compatible_line)
+        } else {
+            None
         }
+    };
+    // sort by name, source and compatible line first so all versions which can
+    // supersede each other are adjacent and ordered by version
+    registry_crates.sort_by(|a, b| {
+        (a.name(), a.source(), compatible_line(a), a.version()).cmp(&(
+            b.name(),
+            b.source(),
+            compatible_line(b),
+            b.version(),
+        ))
+    });
+    for same_crates in registry_crates.chunk_by(|a, b| {
+        a.name() == b.name() && a.source() == b.source() && compatible_line(a) == compatible_line(b)
+    }) {
+        let old_count = same_crates.len().saturating_sub(keep);
+        old_crate_registry.extend_from_slice(&same_crates[..old_count]);
     }
     old_crate_registry.sort();
     old_crate_registry.dedup();
@@ -366,21 +689,84 @@ fn list_old_crates(
     Ok((old_crate_registry, old_crate_git))
 }
 
-/// list used crates
+/// return leftmost non zero version part which semver compatible versions
+/// share. Pre release are part of same line as their final release so they are
+/// superseded by it
+fn semver_compatible_line(version: &Version) -> (u64, u64, u64) {
+    if version.major > 0 {
+        (version.major, 0, 0)
+    } else if version.minor > 0 {
+        (0, version.minor, 0)
+    } else {
+        (0, 0, version.patch)
+    }
+}
+
+/// list all used crates by reading Cargo.lock of scanned projects. Projects
+/// are grouped by workspace root and record crates of their own Cargo.lock.
+/// Ignored Cargo.lock of workspace member still marks its crates as used
 fn list_used_crates(
     config_file: &ConfigFile,
     crate_detail: &CrateDetail,
-) -> Result<(CargoLockFiles, Vec<CrateMetaData>, Vec<CrateMetaData>)> {
+    resolved_locks: &[ResolvedLock],
+    scan_cache: &ScanCache,
+) -> Result<UsedCrates> {
     let mut used_crate_registry = Vec::new();
     let mut used_crate_git = Vec::new();
+    let mut lock_checksums = LockChecksums::new();
     let mut cargo_lock_files = CargoLockFiles::new();
+    let mut manifests = Vec::new();
     let config_directory = config_file.directory().clone();
-    // read a Cargo.lock file and determine out a used registry and git crate
     for path in &config_directory {
-        let list_cargo_locks = config_file.list_cargo_locks(Path::new(path))?;
-        let (mut registry_crate, mut git_crate) =
-            read_content(list_cargo_locks.paths(), crate_detail)?;
-        cargo_lock_files.append(list_cargo_locks);
+        cargo_lock_files.append(config_file.list_cargo_locks(Path::new(path), scan_cache)?);
+        manifests.append(&mut config_file.list_cargo_manifests(Path::new(path), scan_cache)?);
+    }
+    let mut projects = detect_projects(cargo_lock_files.paths(), &manifests);
+    // read a Cargo.lock file and determine out a used registry and git crate
+    for cargo_lock_file in cargo_lock_files.paths() {
+        let Some(project_dir) = cargo_lock_file.parent() else {
+            continue;
+        };
+        let LockFileCrates {
+            registry: mut registry_crate,
+            git: mut git_crate,
+            checksums,
+        } = read_lock_file(cargo_lock_file, project_dir, crate_detail, Some(scan_cache))?;
+        for (crate_metadata, checksum) in checksums {
+            let recorded = lock_checksums.entry(crate_metadata).or_default();
+            if !recorded.iter().any(|(value, _)| *value == checksum) {
+                recorded.push((checksum, cargo_lock_file.clone()));
+            }
+        }
+        for project in &mut projects {
+            if project.lock_file() == Some(cargo_lock_file) {
+                project.add_used_crates(&registry_crate, &git_crate);
+            } else if project.ignored_lock_files().contains(cargo_lock_file) {
+                project.add_ignored_crates(&registry_crate, &git_crate);
+            }
+        }
+        used_crate_registry.append(&mut registry_crate);
+        used_crate_git.append(&mut git_crate);
+    }
+    // Cargo.lock generated for workspace without Cargo.lock. Generated file is
+    // temporary so it is not cached
+    for resolved_lock in resolved_locks {
+        let LockFileCrates {
+            registry: mut registry_crate,
+            git: mut git_crate,
+            ..
+        } = read_lock_file(
+            resolved_lock.lock_file(),
+            resolved_lock.project_dir(),
+            crate_detail,
+            None,
+        )?;
+        if let Some(project) = projects
+            .iter_mut()
+            .find(|project| project.root() == resolved_lock.project_dir())
+        {
+            project.add_resolved_crates(resolved_lock.lock_file(), &registry_crate, &git_crate);
+        }
         used_crate_registry.append(&mut registry_crate);
         used_crate_git.append(&mut git_crate);
     }
@@ -388,7 +774,13 @@ fn list_used_crates(
     used_crate_registry.dedup();
     used_crate_git.sort();
     used_crate_git.dedup();
-    Ok((cargo_lock_files, used_crate_registry, used_crate_git))
+    Ok(UsedCrates {
+        cargo_lock_files,
+        projects,
+        lock_checksums,
+        registry: used_crate_registry,
+        git: used_crate_git,
+    })
 }
 
 /// list orphan crates
@@ -432,6 +824,70 @@ fn list_orphan_crates(
     (orphan_crate_registry, orphan_crate_git)
 }
 
+/// list crates whose last use time is older than provided duration. Crates
+/// without any known last use time are never classified as unused
+fn list_unused_crates(crates: &[CrateMetaData], duration: Duration) -> Vec<CrateMetaData> {
+    let Some(cutoff) = SystemTime::now().checked_sub(duration) else {
+        return Vec::new();
+    };
+    crates
+        .iter()
+        .filter(|crate_metadata| {
+            crate_metadata
+                .last_used()
+                .is_some_and(|last_used| last_used < cutoff)
+        })
+        .cloned()
+        .collect()
+}
+
+/// list registry and git crates to remove, least recently used first, until
+/// size is under max size. Orphan crates are always removed before crates used
+/// by scanned Cargo.lock files and crates without known last use time are
+/// removed last
+fn list_over_budget_crates(
+    installed_crate_registry: &[CrateMetaData],
+    installed_crate_git: &[CrateMetaData],
+    orphan_crate_registry: &[CrateMetaData],
+    orphan_crate_git: &[CrateMetaData],
+    current_size: u64,
+    max_size: u64,
+) -> (Vec<CrateMetaData>, Vec<CrateMetaData>) {
+    let mut over_budget_registry = Vec::new();
+    let mut over_budget_git = Vec::new();
+    // orphan lists are sorted so binary search can be used to find used crates
+    let mut candidates = installed_crate_registry
+        .iter()
+        .map(|crate_metadata| {
+            let is_used = orphan_crate_registry.binary_search(crate_metadata).is_err();
+            (is_used, false, crate_metadata)
+        })
+        .chain(installed_crate_git.iter().map(|crate_metadata| {
+            let is_used = orphan_crate_git.binary_search(crate_metadata).is_err();
+            (is_used, true, crate_metadata)
+        }))
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|(is_used, _, crate_metadata)| {
+        let last_used = crate_metadata.last_used();
+        (*is_used, last_used.is_none(), last_used)
+    });
+    let mut size = current_size;
+    for (_, is_git, crate_metadata) in candidates {
+        if size <= max_size {
+            break;
+        }
+        size = size.saturating_sub(crate_metadata.size());
+        if is_git {
+            over_budget_git.push(crate_metadata.clone());
+        } else {
+            over_budget_registry.push(crate_metadata.clone());
+        }
+    }
+    over_budget_registry.sort();
+    over_budget_git.sort();
+    (over_budget_registry, over_budget_git)
+}
+
 /// get latest commit rev value from git repository
 fn latest_rev_value(path: &Path) -> Result<String> {
     let mut fetch_head_file = PathBuf::new();
@@ -447,41 +903,72 @@ fn latest_rev_value(path: &Path) -> Result<String> {
 
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use semver::Version;
     use url::Url;
 
-    use super::parse_git_source;
+    use super::{
+        GitSource, LockFile, LockSource, list_old_crates, list_over_budget_crates, parse_git_source,
+    };
+    use crate::crate_detail::CrateMetaData;
+    use crate::crate_spec::CrateSpec;
+
+    fn used_meta(name: &str, size: u64, last_used_secs: u64) -> CrateMetaData {
+        CrateMetaData::new(
+            name.to_string(),
+            Some(Version::parse("1.0.0").unwrap()),
+            Some("registry".to_string()),
+        )
+        .with_usage(
+            size,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(last_used_secs)),
+        )
+    }
 
     #[test]
     fn parse_git_source_plain_hash_test() {
-        let (url, sha) =
+        let git_source =
             parse_git_source("git+https://github.com/foo/bar#0123456789abcdef0123456789ab")
                 .unwrap();
-        assert_eq!(url, Url::parse("https://github.com/foo/bar").unwrap());
-        assert_eq!(sha, "0123456");
+        assert_eq!(
+            git_source.url,
+            Url::parse("https://github.com/foo/bar").unwrap()
+        );
+        assert_eq!(git_source.short_id(), "0123456");
     }
 
     #[test]
     fn parse_git_source_rev_query_test() {
-        let (url, sha) =
+        let git_source =
             parse_git_source("git+https://github.com/foo/bar?rev=v1.2.3#abcdef1234567890").unwrap();
-        assert_eq!(url, Url::parse("https://github.com/foo/bar").unwrap());
-        assert_eq!(sha, "abcdef1");
+        assert_eq!(
+            git_source.url,
+            Url::parse("https://github.com/foo/bar").unwrap()
+        );
+        assert_eq!(git_source.short_id(), "abcdef1");
     }
 
     #[test]
     fn parse_git_source_branch_query_test() {
-        let (url, sha) =
+        let git_source =
             parse_git_source("git+https://github.com/foo/bar?branch=main#deadbeefcafe0").unwrap();
-        assert_eq!(url, Url::parse("https://github.com/foo/bar").unwrap());
-        assert_eq!(sha, "deadbee");
+        assert_eq!(
+            git_source.url,
+            Url::parse("https://github.com/foo/bar").unwrap()
+        );
+        assert_eq!(git_source.short_id(), "deadbee");
     }
 
     #[test]
     fn parse_git_source_tag_query_test() {
-        let (url, sha) =
+        let git_source =
             parse_git_source("git+https://github.com/foo/bar?tag=v0.1.0#0f1e2d3c4b5a").unwrap();
-        assert_eq!(url, Url::parse("https://github.com/foo/bar").unwrap());
-        assert_eq!(sha, "0f1e2d3");
+        assert_eq!(
+            git_source.url,
+            Url::parse("https://github.com/foo/bar").unwrap()
+        );
+        assert_eq!(git_source.short_id(), "0f1e2d3");
     }
 
     #[test]
@@ -498,4 +985,229 @@ mod tests {
     fn parse_git_source_rev_without_hash_is_error_test() {
         assert!(parse_git_source("git+https://github.com/foo/bar?rev=v1").is_err());
     }
+
+    #[test]
+    fn over_budget_removes_least_recently_used_first_test() {
+        let old = used_meta("old", 100, 10);
+        let recent = used_meta("recent", 100, 30);
+        let middle = used_meta("middle", 100, 20);
+        let mut installed = vec![old.clone(), recent, middle.clone()];
+        installed.sort();
+        let orphan = installed.clone();
+        let (registry, git) = list_over_budget_crates(&installed, &[], &orphan, &[], 300, 150);
+        assert_eq!(registry, vec![middle, old]);
+        assert!(git.is_empty());
+    }
+
+    #[test]
+    fn over_budget_removes_orphan_before_used_test() {
+        let used_old = used_meta("used", 100, 10);
+        let orphan_recent = used_meta("orphan", 100, 30);
+        let mut installed = vec![used_old.clone(), orphan_recent.clone()];
+        installed.sort();
+        let orphan = vec![orphan_recent.clone()];
+        let (registry, _) = list_over_budget_crates(&installed, &[], &orphan, &[], 200, 100);
+        assert_eq!(registry, vec![orphan_recent.clone()]);
+        let (registry, _) = list_over_budget_crates(&installed, &[], &orphan, &[], 200, 50);
+        assert_eq!(registry, vec![orphan_recent, used_old]);
+    }
+
+    #[test]
+    fn over_budget_removes_unknown_last_used_last_test() {
+        let unknown = CrateMetaData::new(
+            "unknown".to_string(),
+            Some(Version::parse("1.0.0").unwrap()),
+            Some("registry".to_string()),
+        )
+        .with_usage(100, None);
+        let recent = used_meta("recent", 100, 30);
+        let mut installed = vec![unknown.clone(), recent.clone()];
+        installed.sort();
+        let (registry, _) = list_over_budget_crates(&installed, &[], &installed, &[], 200, 100);
+        assert_eq!(registry, vec![recent.clone()]);
+        let (registry, _) = list_over_budget_crates(&installed, &[], &installed, &[], 200, 0);
+        assert_eq!(registry, vec![recent, unknown]);
+    }
+
+    #[test]
+    fn over_budget_under_limit_removes_nothing_test() {
+        let installed = vec![used_meta("serde", 100, 10)];
+        let (registry, git) = list_over_budget_crates(&installed, &[], &installed, &[], 100, 100);
+        assert!(registry.is_empty());
+        assert!(git.is_empty());
+    }
+
+    fn versioned_meta(name: &str, version: &str, source: &str) -> CrateMetaData {
+        CrateMetaData::new(
+            name.to_string(),
+            Some(Version::parse(version).unwrap()),
+            Some(source.to_string()),
+        )
+    }
+
+    #[test]
+    fn list_old_crates_keeps_newest_versions_test() {
+        let installed = vec![
+            versioned_meta("serde", "1.0.2", "registry"),
+            versioned_meta("serde", "1.0.10", "registry"),
+            versioned_meta("serde", "1.0.1", "registry"),
+            versioned_meta("serde", "1.0.1", "mirror"),
+            versioned_meta("syn", "2.0.0", "registry"),
+        ];
+        let db_dir = std::env::temp_dir().join("cargo-trim-missing-db-dir");
+        let (old, _) = list_old_crates(&db_dir, installed.clone(), &[], 1, false).unwrap();
+        assert_eq!(
+            old,
+            vec![
+                versioned_meta("serde", "1.0.1", "registry"),
+                versioned_meta("serde", "1.0.2", "registry"),
+            ]
+        );
+        let (old, _) = list_old_crates(&db_dir, installed.clone(), &[], 2, false).unwrap();
+        assert_eq!(old, vec![versioned_meta("serde", "1.0.1", "registry")]);
+        let (old, _) = list_old_crates(&db_dir, installed, &[], 3, false).unwrap();
+        assert!(old.is_empty());
+    }
+
+    #[test]
+    fn list_old_crates_semver_compatible_test() {
+        let installed = vec![
+            versioned_meta("syn", "1.0.108", "registry"),
+            versioned_meta("syn", "1.0.109", "registry"),
+            versioned_meta("syn", "2.0.0-rc.1", "registry"),
+            versioned_meta("syn", "2.0.0", "registry"),
+            versioned_meta("rand", "0.7.3", "registry"),
+            versioned_meta("rand", "0.8.5", "registry"),
+            versioned_meta("rand", "0.8.4", "registry"),
+            versioned_meta("tiny", "0.0.1", "registry"),
+            versioned_meta("tiny", "0.0.2", "registry"),
+        ];
+        let db_dir = std::env::temp_dir().join("cargo-trim-missing-db-dir");
+        let (old, _) = list_old_crates(&db_dir, installed.clone(), &[], 1, true).unwrap();
+        assert_eq!(
+            old,
+            vec![
+                versioned_meta("rand", "0.8.4", "registry"),
+                versioned_meta("syn", "1.0.108", "registry"),
+                versioned_meta("syn", "2.0.0-rc.1", "registry"),
+            ]
+        );
+        // without semver compatible mode every version except newest one is old
+        let (old, _) = list_old_crates(&db_dir, installed, &[], 1, false).unwrap();
+        assert_eq!(old.len(), 6);
+    }
+
+    const SERDE_CHECKSUM: &str = "f4473e8d7f0f3a8e6b5b1c2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60";
+
+    // check lockfile fixture contains path package app, git package bar and
+    // registry package serde
+    fn assert_lock_file(content: &str, serde_source: LockSource) {
+        let lock_file = LockFile::parse(content).unwrap();
+        assert_eq!(lock_file.packages.len(), 3);
+        let app = &lock_file.packages[0];
+        assert_eq!(app.name, "app");
+        assert!(app.source.is_none());
+
+        let bar = &lock_file.packages[1];
+        assert_eq!(bar.name, "bar");
+        assert!(bar.checksum.is_none());
+        assert_eq!(
+            bar.source,
+            Some(LockSource::Git(GitSource {
+                url: Url::parse("https://github.com/foo/bar").unwrap(),
+                precise: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            }))
+        );
+
+        let serde = &lock_file.packages[2];
+        assert_eq!(serde.version, Version::parse("1.0.100").unwrap());
+        assert_eq!(serde.checksum.as_deref(), Some(SERDE_CHECKSUM));
+        assert_eq!(serde.source, Some(serde_source));
+    }
+
+    fn crates_io_git() -> LockSource {
+        LockSource::Registry(Url::parse("https://github.com/rust-lang/crates.io-index").unwrap())
+    }
+
+    #[test]
+    fn lock_file_v1_test() {
+        assert_lock_file(
+            include_str!("../tests/fixtures/cargo_lock/v1.lock"),
+            crates_io_git(),
+        );
+    }
+
+    #[test]
+    fn lock_file_v2_test() {
+        assert_lock_file(
+            include_str!("../tests/fixtures/cargo_lock/v2.lock"),
+            crates_io_git(),
+        );
+    }
+
+    #[test]
+    fn lock_file_v3_test() {
+        assert_lock_file(
+            include_str!("../tests/fixtures/cargo_lock/v3.lock"),
+            LockSource::Sparse(Url::parse("sparse+https://index.crates.io/").unwrap()),
+        );
+    }
+
+    #[test]
+    fn lock_file_v4_test() {
+        assert_lock_file(
+            include_str!("../tests/fixtures/cargo_lock/v4.lock"),
+            crates_io_git(),
+        );
+    }
+
+    #[test]
+    fn dependency_paths_test() {
+        let lock_file = LockFile::parse(
+            r#"
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["bar", "serde 1.0.200", "toml"]
+
+[[package]]
+name = "bar"
+version = "0.2.0"
+source = "git+https://github.com/foo/bar#0123456789abcdef0123456789abcdef01234567"
+
+[[package]]
+name = "serde"
+version = "1.0.100"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "toml"
+version = "0.8.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["serde 1.0.100"]
+"#,
+        )
+        .unwrap();
+        let paths = |spec: &str| lock_file.dependency_paths(&CrateSpec::parse(spec).unwrap());
+        assert_eq!(
+            paths("serde"),
+            [
+                vec!["app v0.1.0", "toml v0.8.0", "serde v1.0.100"],
+                vec!["app v0.1.0", "serde v1.0.200"]
+            ]
+        );
+        assert_eq!(
+            paths("serde@1.0.200"),
+            [vec!["app v0.1.0", "serde v1.0.200"]]
+        );
+        assert_eq!(paths("bar-0123456"), [vec!["app v0.1.0", "bar v0.2.0"]]);
+        assert!(paths("rand").is_empty());
+    }
 }
@@ -1,10 +1,24 @@
+mod bin_dir;
+mod cache_lock;
+mod cargo_config;
 mod command;
 mod config_file;
 mod crate_detail;
+mod crate_spec;
+mod dedup;
 mod dir_path;
+mod dir_size;
 mod git_dir;
+mod global_cache;
 mod list_crate;
+mod missing_lock;
+mod project;
+mod protect;
 mod registry_dir;
+mod scan_cache;
+mod source_hash;
+mod target_dir;
+mod trash;
 mod utils;
 
 use std::env;
@@ -0,0 +1,366 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context as _, Result};
+use owo_colors::OwoColorize as _;
+use tempfile::TempDir;
+
+use crate::config_file::ConfigFile;
+use crate::project::{normalize_path, workspace_root};
+use crate::scan_cache::ScanCache;
+use crate::utils::print_message;
+
+/// Cargo.lock generated inside temporary directory for workspace which doesn't
+/// have Cargo.lock. Temporary directory is removed when dropped
+pub(crate) struct ResolvedLock {
+    _temp_dir: TempDir,
+    lock_file: PathBuf,
+    project_dir: PathBuf,
+}
+
+impl ResolvedLock {
+    /// generate Cargo.lock for workspace of root manifest. Only manifests
+    /// along with empty stub of target files are copied to temporary directory
+    /// so project directory is never modified
+    fn generate(root_manifest: &Path) -> Result<Self> {
+        let project_dir = root_manifest
+            .parent()
+            .context("failed to get workspace directory")?;
+        let temp_dir = tempfile::Builder::new()
+            .prefix("cargo-trim-lock")
+            .tempdir()
+            .context("failed to create temporary directory")?;
+        let root_manifest = normalize_path(
+            &std::path::absolute(root_manifest).context("failed to get absolute manifest path")?,
+        );
+        copy_manifests(&root_manifest, temp_dir.path())?;
+        let copy_manifest = mirror_path(temp_dir.path(), &root_manifest);
+        // run inside project directory so cargo config of project is used
+        let output = Command::new("cargo")
+            .arg("generate-lockfile")
+            .arg("--offline")
+            .arg("--manifest-path")
+            .arg(&copy_manifest)
+            .current_dir(project_dir)
+            .output()
+            .context("failed to run cargo generate-lockfile command")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "cargo generate-lockfile failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(Self {
+            _temp_dir: temp_dir,
+            lock_file: copy_manifest.with_file_name("Cargo.lock"),
+            project_dir: project_dir.to_path_buf(),
+        })
+    }
+
+    /// return path of generated Cargo.lock
+    pub(crate) fn lock_file(&self) -> &Path {
+        &self.lock_file
+    }
+
+    /// return workspace directory for which Cargo.lock was generated
+    pub(crate) fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+}
+
+/// generate Cargo.lock for all workspaces of config directories which don't
+/// have Cargo.lock. Workspace whose Cargo.lock cannot be generated is skipped
+pub(crate) fn resolve_missing_locks(
+    config_file: &ConfigFile,
+    scan_cache: &ScanCache,
+) -> Result<Vec<ResolvedLock>> {
+    let mut manifests = Vec::new();
+    for directory in config_file.directory() {
+        manifests.append(&mut config_file.list_cargo_manifests(Path::new(directory), scan_cache)?);
+    }
+    let mut resolved_locks = Vec::new();
+    for root_manifest in missing_lock_roots(&manifests) {
+        match ResolvedLock::generate(&root_manifest) {
+            Ok(resolved_lock) => {
+                print_message(format_args!(
+                    "{} missing Cargo.lock of {}",
+                    "Resolved".green(),
+                    resolved_lock.project_dir().display()
+                ));
+                resolved_locks.push(resolved_lock);
+            }
+            Err(err) => {
+                print_message(format_args!(
+                    "{} to resolve missing Cargo.lock of {}: {err}",
+                    "Failed".red(),
+                    root_manifest.display()
+                ));
+            }
+        }
+    }
+    Ok(resolved_locks)
+}
+
+/// list workspace root manifests of provided manifests which don't have
+/// Cargo.lock
+fn missing_lock_roots(manifests: &[PathBuf]) -> BTreeSet<PathBuf> {
+    manifests
+        .iter()
+        .filter(|manifest| !manifest.with_file_name("Cargo.lock").exists())
+        .filter_map(|manifest| workspace_root(manifest))
+        .filter(|root| !root.with_file_name("Cargo.lock").exists())
+        .collect()
+}
+
+/// return path inside destination which mirrors absolute path. Mirroring
+/// whole path keeps relative path between copied manifests same
+fn mirror_path(destination: &Path, path: &Path) -> PathBuf {
+    let mut mirrored = destination.to_path_buf();
+    for component in path.components() {
+        if let Component::Normal(name) = component {
+            mirrored.push(name);
+        }
+    }
+    mirrored
+}
+
+/// copy root manifest along with manifests of workspace members, path
+/// dependencies, patches and their workspace roots to destination. Manifest
+/// which cannot be read is skipped so cargo reports it while resolving
+fn copy_manifests(root_manifest: &Path, destination: &Path) -> Result<()> {
+    let mut pending = vec![root_manifest.to_path_buf()];
+    let mut copied = HashSet::new();
+    while let Some(manifest_path) = pending.pop() {
+        if !copied.insert(manifest_path.clone()) {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&manifest_path) else {
+            continue;
+        };
+        let copy_path = mirror_path(destination, &manifest_path);
+        if let Some(parent) = copy_path.parent() {
+            fs::create_dir_all(parent).context("failed to create manifest copy directory")?;
+        }
+        fs::write(&copy_path, &content)
+            .with_context(|| format!("failed to copy {}", manifest_path.display()))?;
+        let Some(package_dir) = manifest_path.parent() else {
+            continue;
+        };
+        let manifest = toml::from_str::<toml::Table>(&content).unwrap_or_default();
+        create_stub_files(package_dir, destination, &manifest)?;
+        pending.extend(
+            referenced_manifests(package_dir, &manifest)
+                .into_iter()
+                .map(|path| normalize_path(&path)),
+        );
+        pending.extend(workspace_root(&manifest_path));
+    }
+    Ok(())
+}
+
+/// list manifests referenced by manifest present in package directory
+fn referenced_manifests(package_dir: &Path, manifest: &toml::Table) -> Vec<PathBuf> {
+    let mut package_dirs = Vec::new();
+    if let Some(workspace) = manifest
+        .get("package")
+        .and_then(|package| package.get("workspace"))
+        .and_then(toml::Value::as_str)
+    {
+        package_dirs.push(package_dir.join(workspace));
+    }
+    let workspace = manifest.get("workspace").and_then(toml::Value::as_table);
+    for member in workspace
+        .and_then(|workspace| workspace.get("members"))
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(toml::Value::as_str)
+    {
+        let pattern = package_dir.join(member);
+        match glob::glob(&pattern.to_string_lossy()) {
+            Ok(paths) => package_dirs.extend(paths.flatten()),
+            Err(_) => package_dirs.push(pattern),
+        }
+    }
+
+    // dependency tables whose path dependency can be used while resolving
+    let targets = manifest
+        .get("target")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flat_map(toml::Table::values)
+        .filter_map(toml::Value::as_table);
+    let mut dependency_tables = Vec::new();
+    for table in std::iter::once(manifest).chain(workspace).chain(targets) {
+        for kind in [
+            "dependencies",
+            "dev-dependencies",
+            "dev_dependencies",
+            "build-dependencies",
+            "build_dependencies",
+        ] {
+            dependency_tables.extend(table.get(kind).and_then(toml::Value::as_table));
+        }
+    }
+    dependency_tables.extend(
+        manifest
+            .get("patch")
+            .and_then(toml::Value::as_table)
+            .into_iter()
+            .flat_map(toml::Table::values)
+            .filter_map(toml::Value::as_table),
+    );
+    dependency_tables.extend(manifest.get("replace").and_then(toml::Value::as_table));
+    for dependency in dependency_tables.into_iter().flat_map(toml::Table::values) {
+        if let Some(path) = dependency.get("path").and_then(toml::Value::as_str) {
+            package_dirs.push(package_dir.join(path));
+        }
+    }
+    package_dirs
+        .into_iter()
+        .map(|package_dir| package_dir.join("Cargo.toml"))
+        .collect()
+}
+
+/// create empty stub of build script and target files of package so cargo
+/// discovers same targets from copied manifest
+fn create_stub_files(package_dir: &Path, destination: &Path, manifest: &toml::Table) -> Result<()> {
+    let mut files = vec![
+        PathBuf::from("build.rs"),
+        PathBuf::from("src/lib.rs"),
+        PathBuf::from("src/main.rs"),
+    ];
+    for target_dir in ["src/bin", "examples", "tests", "benches"] {
+        let Ok(entries) = fs::read_dir(package_dir.join(target_dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = Path::new(target_dir).join(entry.file_name());
+            files.push(path.join("main.rs"));
+            files.push(path);
+        }
+    }
+    files.extend(
+        manifest
+            .get("package")
+            .and_then(|package| package.get("build"))
+            .and_then(toml::Value::as_str)
+            .map(PathBuf::from),
+    );
+    let targets = ["bin", "example", "test", "bench"]
+        .into_iter()
+        .filter_map(|kind| manifest.get(kind).and_then(toml::Value::as_array))
+        .flatten()
+        .chain(manifest.get("lib"));
+    files.extend(
+        targets
+            .filter_map(|target| target.get("path").and_then(toml::Value::as_str))
+            .map(PathBuf::from),
+    );
+    for file in files {
+        let path = normalize_path(&package_dir.join(file));
+        if !path.is_file() {
+            continue;
+        }
+        let stub_path = mirror_path(destination, &path);
+        if let Some(parent) = stub_path.parent() {
+            fs::create_dir_all(parent).context("failed to create stub directory")?;
+        }
+        fs::write(&stub_path, "")
+            .with_context(|| format!("failed to create stub of {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use super::{ResolvedLock, missing_lock_roots};
+
+    fn write_manifest(path: &PathBuf, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn missing_lock_roots_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let workspace = root.join("workspace/Cargo.toml");
+        let member = root.join("workspace/member/Cargo.toml");
+        let locked = root.join("locked/Cargo.toml");
+        let library = root.join("library/Cargo.toml");
+        let outside = root.join("outside/Cargo.toml");
+        write_manifest(
+            &workspace,
+            "[workspace]\nmembers = [\"member\", \"../outside\"]\n",
+        );
+        write_manifest(&member, "[package]\nname = \"member\"\n");
+        write_manifest(
+            &outside,
+            "[package]\nname = \"outside\"\nworkspace = \"../workspace\"\n",
+        );
+        write_manifest(&locked, "[package]\nname = \"locked\"\n");
+        fs::write(root.join("locked/Cargo.lock"), "version = 4\n").unwrap();
+        write_manifest(&library, "[package]\nname = \"library\"\n");
+
+        let roots = missing_lock_roots(&[member.clone(), outside, locked, library.clone()]);
+        assert_eq!(roots.into_iter().collect::<Vec<_>>(), [library, workspace]);
+
+        fs::write(root.join("workspace/Cargo.lock"), "version = 4\n").unwrap();
+        assert!(missing_lock_roots(&[member]).is_empty());
+    }
+
+    #[test]
+    fn generate_lock_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let workspace = root.join("workspace");
+        write_manifest(
+            &workspace.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\", \"../outside\"]\n",
+        );
+        write_manifest(
+            &workspace.join("crates/member/Cargo.toml"),
+            "[package]\nname = \"member\"\nedition = \"2021\"\n\n[dependencies]\nshared = { path \
+             = \"../../../shared\" }\n",
+        );
+        fs::create_dir_all(workspace.join("crates/member/src/bin")).unwrap();
+        fs::write(
+            workspace.join("crates/member/src/bin/tool.rs"),
+            "fn main() {}",
+        )
+        .unwrap();
+        write_manifest(
+            &root.join("outside/Cargo.toml"),
+            "[package]\nname = \"outside\"\nedition = \"2021\"\nworkspace = \
+             \"../workspace\"\n\n[lib]\npath = \"lib.rs\"\n",
+        );
+        fs::write(root.join("outside/lib.rs"), "pub fn outside() {}").unwrap();
+        write_manifest(
+            &root.join("shared/Cargo.toml"),
+            "[package]\nname = \"shared\"\nedition = \"2021\"\n",
+        );
+        fs::create_dir_all(root.join("shared/src")).unwrap();
+        fs::write(root.join("shared/src/lib.rs"), "pub fn shared() {}").unwrap();
+
+        let resolved_lock = ResolvedLock::generate(&workspace.join("Cargo.toml")).unwrap();
+        let lock_file = fs::read_to_string(resolved_lock.lock_file()).unwrap();
+        for name in ["member", "outside", "shared"] {
+            assert!(lock_file.contains(&format!("name = \"{name}\"")));
+        }
+        // project directory is never modified
+        assert!(!workspace.join("Cargo.lock").exists());
+        assert_eq!(
+            fs::read_to_string(root.join("shared/src/lib.rs")).unwrap(),
+            "pub fn shared() {}"
+        );
+        drop(resolved_lock);
+    }
+}
@@ -0,0 +1,474 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::crate_detail::CrateMetaData;
+
+#[derive(Deserialize)]
+struct Manifest {
+    workspace: Option<ManifestWorkspace>,
+    package: Option<ManifestPackage>,
+}
+
+#[derive(Deserialize)]
+struct ManifestWorkspace {
+    members: Option<Vec<String>>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+impl ManifestWorkspace {
+    /// check if manifest is excluded from workspace of root directory. Same
+    /// as cargo path matching excluded directory is skipped unless it is also
+    /// matched by members path
+    fn is_excluded(&self, root_dir: &Path, manifest_path: &Path) -> bool {
+        let matches = |paths: &[String]| {
+            paths
+                .iter()
+                .any(|path| manifest_path.starts_with(normalize_path(&root_dir.join(path))))
+        };
+        let explicit_member = self.members.as_deref().is_some_and(matches);
+        !explicit_member && matches(&self.exclude)
+    }
+}
+
+#[derive(Deserialize)]
+struct ManifestPackage {
+    workspace: Option<String>,
+}
+
+/// Registry and git crates read from Cargo.lock files
+#[derive(Default)]
+struct LockCrates {
+    registry: Vec<CrateMetaData>,
+    git: Vec<CrateMetaData>,
+}
+
+impl LockCrates {
+    fn add(&mut self, registry_crates: &[CrateMetaData], git_crates: &[CrateMetaData]) {
+        self.registry.extend_from_slice(registry_crates);
+        self.registry.sort();
+        self.registry.dedup();
+        self.git.extend_from_slice(git_crates);
+        self.git.sort();
+        self.git.dedup();
+    }
+}
+
+/// Rust project detected while scanning directories. Workspace members are
+/// grouped under workspace root since cargo only uses Cargo.lock of root
+pub(crate) struct Project {
+    root: PathBuf,
+    members: Vec<PathBuf>,
+    lock_file: Option<PathBuf>,
+    resolved_lock_file: Option<PathBuf>,
+    ignored_lock_files: Vec<PathBuf>,
+    used_crates: LockCrates,
+    ignored_crates: LockCrates,
+    pinned_size: u64,
+}
+
+impl Project {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            members: Vec::new(),
+            lock_file: None,
+            resolved_lock_file: None,
+            ignored_lock_files: Vec::new(),
+            used_crates: LockCrates::default(),
+            ignored_crates: LockCrates::default(),
+            pinned_size: 0,
+        }
+    }
+
+    /// return workspace root directory or package directory of project
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// return member directories of workspace except root directory
+    pub(crate) fn members(&self) -> &[PathBuf] {
+        &self.members
+    }
+
+    /// return Cargo.lock used by cargo for project
+    pub(crate) fn lock_file(&self) -> Option<&PathBuf> {
+        self.lock_file.as_ref()
+    }
+
+    /// return Cargo.lock of workspace members which are ignored by cargo
+    pub(crate) fn ignored_lock_files(&self) -> &[PathBuf] {
+        &self.ignored_lock_files
+    }
+
+    /// return all Cargo.lock files read for project. Cargo.lock used by cargo
+    /// or generated for project without Cargo.lock comes first
+    pub(crate) fn read_lock_files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.lock_file
+            .iter()
+            .chain(&self.resolved_lock_file)
+            .chain(&self.ignored_lock_files)
+    }
+
+    /// return number of registry dependencies present in Cargo.lock
+    pub(crate) fn registry_dependency_count(&self) -> usize {
+        let mut dependencies = self
+            .used_crates
+            .registry
+            .iter()
+            .map(|crate_metadata| (crate_metadata.name(), crate_metadata.version()))
+            .collect::<Vec<_>>();
+        dependencies.dedup();
+        dependencies.len()
+    }
+
+    /// return number of git dependencies present in Cargo.lock
+    pub(crate) fn git_dependency_count(&self) -> usize {
+        let mut dependencies = self
+            .used_crates
+            .git
+            .iter()
+            .map(CrateMetaData::name)
+            .collect::<Vec<_>>();
+        dependencies.dedup();
+        dependencies.len()
+    }
+
+    /// return size of installed crates which are used by project
+    pub(crate) fn pinned_size(&self) -> u64 {
+        self.pinned_size
+    }
+
+    /// add crates read from Cargo.lock used by project
+    pub(crate) fn add_used_crates(
+        &mut self,
+        registry_crates: &[CrateMetaData],
+        git_crates: &[CrateMetaData],
+    ) {
+        self.used_crates.add(registry_crates, git_crates);
+    }
+
+    /// add crates read from Cargo.lock generated for project without
+    /// Cargo.lock
+    pub(crate) fn add_resolved_crates(
+        &mut self,
+        resolved_lock_file: &Path,
+        registry_crates: &[CrateMetaData],
+        git_crates: &[CrateMetaData],
+    ) {
+        self.resolved_lock_file = Some(resolved_lock_file.to_path_buf());
+        self.used_crates.add(registry_crates, git_crates);
+    }
+
+    /// add crates read from Cargo.lock of workspace member which is ignored by
+    /// cargo. These crates are not counted as dependencies but still belong to
+    /// project directory
+    pub(crate) fn add_ignored_crates(
+        &mut self,
+        registry_crates: &[CrateMetaData],
+        git_crates: &[CrateMetaData],
+    ) {
+        self.ignored_crates.add(registry_crates, git_crates);
+    }
+
+    /// check if root or member directory of project is provided canonical
+    /// directory. Project directories are canonicalized before comparison
+    /// since scanned directory can be reached through symlink
+    pub(crate) fn contains_dir(&self, dir: &Path) -> bool {
+        std::iter::once(&self.root)
+            .chain(&self.members)
+            .any(|project_dir| {
+                fs::canonicalize(project_dir).unwrap_or_else(|_| project_dir.clone()) == dir
+            })
+    }
+
+    /// check if registry crate is referenced by any Cargo.lock of project
+    pub(crate) fn references_registry(&self, crate_metadata: &CrateMetaData) -> bool {
+        self.used_crates
+            .registry
+            .binary_search(crate_metadata)
+            .is_ok()
+            || self
+                .ignored_crates
+                .registry
+                .binary_search(crate_metadata)
+                .is_ok()
+    }
+
+    /// check if git crate is referenced by any Cargo.lock of project
+    pub(crate) fn references_git(&self, crate_metadata: &CrateMetaData) -> bool {
+        self.used_crates.git.binary_search(crate_metadata).is_ok()
+            || self
+                .ignored_crates
+                .git
+                .binary_search(crate_metadata)
+                .is_ok()
+    }
+
+    /// compute size of installed crates pinned by project. Installed crate
+    /// list needs to be sorted
+    pub(crate) fn compute_pinned_size(
+        &mut self,
+        installed_crate_registry: &[CrateMetaData],
+        installed_crate_git: &[CrateMetaData],
+    ) {
+        let pinned = |used: &[CrateMetaData], installed: &[CrateMetaData]| {
+            used.iter()
+                .filter_map(|crate_metadata| {
+                    installed
+                        .binary_search(crate_metadata)
+                        .ok()
+                        .map(|index| installed[index].size())
+                })
+                .sum::<u64>()
+        };
+        self.pinned_size = pinned(&self.used_crates.registry, installed_crate_registry)
+            + pinned(&self.used_crates.git, installed_crate_git);
+    }
+}
+
+/// group scanned Cargo.lock and Cargo.toml files into projects. Cargo.lock of
+/// workspace member is recorded as ignored lock file of workspace root
+pub(crate) fn detect_projects(lock_files: &[PathBuf], manifests: &[PathBuf]) -> Vec<Project> {
+    let mut projects = BTreeMap::new();
+    for manifest in manifests {
+        let Some(dir) = manifest.parent() else {
+            continue;
+        };
+        let root = project_root(manifest);
+        let project = projects
+            .entry(root.clone())
+            .or_insert_with(|| Project::new(root.clone()));
+        if dir != root {
+            project.members.push(dir.to_path_buf());
+        }
+    }
+    for lock_file in lock_files {
+        let Some(dir) = lock_file.parent() else {
+            continue;
+        };
+        let manifest = dir.join("Cargo.toml");
+        // Cargo.lock without manifest is kept as its own project
+        let root = if manifest.is_file() {
+            project_root(&manifest)
+        } else {
+            dir.to_path_buf()
+        };
+        let project = projects
+            .entry(root.clone())
+            .or_insert_with(|| Project::new(root.clone()));
+        if dir == root {
+            project.lock_file = Some(lock_file.clone());
+        } else {
+            project.ignored_lock_files.push(lock_file.clone());
+        }
+    }
+    projects
+        .into_values()
+        .map(|mut project| {
+            project.members.sort();
+            project.members.dedup();
+            project.ignored_lock_files.sort();
+            project
+        })
+        .collect()
+}
+
+/// return directory of workspace root of manifest. Directory of manifest is
+/// returned when workspace root cannot be found
+fn project_root(manifest: &Path) -> PathBuf {
+    let dir = manifest.parent().unwrap_or(manifest);
+    workspace_root(manifest)
+        .and_then(|root| root.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| dir.to_path_buf())
+}
+
+/// remove `.` and `..` component from path without accessing file system
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// find root manifest of workspace which contains manifest. Workspace of
+/// parent directory which excludes manifest is skipped. Manifest itself is
+/// root when it is not member of any workspace. Returned path is normalized
+pub(crate) fn workspace_root(manifest_path: &Path) -> Option<PathBuf> {
+    let manifest = read_manifest(manifest_path)?;
+    let dir = manifest_path.parent()?;
+    if manifest.workspace.is_some() {
+        return Some(manifest_path.to_path_buf());
+    }
+    if let Some(root) = manifest.package.and_then(|package| package.workspace) {
+        return Some(normalize_path(&dir.join(root).join("Cargo.toml")));
+    }
+    for ancestor in dir.ancestors().skip(1) {
+        let candidate = ancestor.join("Cargo.toml");
+        if candidate.is_file()
+            && read_manifest(&candidate)
+                .and_then(|manifest| manifest.workspace)
+                .is_some_and(|workspace| !workspace.is_excluded(ancestor, manifest_path))
+        {
+            return Some(candidate);
+        }
+    }
+    Some(manifest_path.to_path_buf())
+}
+
+/// read manifest. Return none when manifest is invalid
+fn read_manifest(manifest_path: &Path) -> Option<Manifest> {
+    let content = fs::read_to_string(manifest_path).ok()?;
+    toml::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use super::{Project, detect_projects};
+    use crate::crate_detail::CrateMetaData;
+
+    fn write_file(path: &PathBuf, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn detect_projects_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let workspace = root.join("workspace");
+        let member = workspace.join("crates/member");
+        let excluded = workspace.join("crates/excluded");
+        let outside_member = root.join("outside");
+        let single = root.join("single");
+        let stray = root.join("stray");
+        write_file(
+            &workspace.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/member\", \"../outside\"]\nexclude = [\"crates\"]\n",
+        );
+        write_file(&member.join("Cargo.toml"), "[package]\nname = \"member\"\n");
+        write_file(
+            &excluded.join("Cargo.toml"),
+            "[package]\nname = \"excluded\"\n",
+        );
+        write_file(
+            &outside_member.join("Cargo.toml"),
+            "[package]\nname = \"outside\"\nworkspace = \"../workspace\"\n",
+        );
+        write_file(&single.join("Cargo.toml"), "[package]\nname = \"single\"\n");
+        let lock_files = [
+            workspace.join("Cargo.lock"),
+            member.join("Cargo.lock"),
+            excluded.join("Cargo.lock"),
+            stray.join("Cargo.lock"),
+        ];
+        for lock_file in &lock_files {
+            write_file(lock_file, "version = 4\n");
+        }
+        let manifests = [
+            workspace.join("Cargo.toml"),
+            member.join("Cargo.toml"),
+            excluded.join("Cargo.toml"),
+            outside_member.join("Cargo.toml"),
+            single.join("Cargo.toml"),
+        ];
+
+        let projects = detect_projects(&lock_files, &manifests);
+        let summary = projects
+            .iter()
+            .map(|project| {
+                (
+                    project.root().to_path_buf(),
+                    project.members().to_vec(),
+                    project.lock_file().cloned(),
+                    project.ignored_lock_files().to_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (single, vec![], None, vec![]),
+                (
+                    stray.clone(),
+                    vec![],
+                    Some(stray.join("Cargo.lock")),
+                    vec![]
+                ),
+                (
+                    workspace.clone(),
+                    vec![outside_member, member.clone()],
+                    Some(workspace.join("Cargo.lock")),
+                    vec![member.join("Cargo.lock")]
+                ),
+                (
+                    excluded.clone(),
+                    vec![],
+                    Some(excluded.join("Cargo.lock")),
+                    vec![]
+                ),
+            ]
+        );
+    }
+
+    fn registry_crate(name: &str) -> CrateMetaData {
+        CrateMetaData::new(
+            name.to_string(),
+            Some(semver::Version::new(1, 0, 0)),
+            Some("index.crates.io-1949cf8c6b5b557f".to_string()),
+        )
+    }
+
+    #[test]
+    fn project_references_ignored_lock_crates_test() {
+        let mut project = Project::new(PathBuf::from("/workspace"));
+        project.add_used_crates(&[registry_crate("serde"), registry_crate("anyhow")], &[]);
+        project.add_ignored_crates(&[registry_crate("rand")], &[]);
+        assert_eq!(project.registry_dependency_count(), 2);
+        assert!(project.references_registry(&registry_crate("anyhow")));
+        assert!(project.references_registry(&registry_crate("rand")));
+        assert!(!project.references_registry(&registry_crate("url")));
+        assert!(!project.references_git(&registry_crate("serde")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn contains_symlinked_dir_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let workspace = root.join("workspace");
+        let member = workspace.join("member");
+        write_file(
+            &workspace.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"member\"]\n",
+        );
+        write_file(&member.join("Cargo.toml"), "[package]\nname = \"member\"\n");
+        let link = root.join("link");
+        std::os::unix::fs::symlink(&workspace, &link).unwrap();
+
+        let projects = detect_projects(
+            &[],
+            &[link.join("Cargo.toml"), link.join("member/Cargo.toml")],
+        );
+        assert_eq!(projects.len(), 1);
+        let member = fs::canonicalize(&member).unwrap();
+        assert!(projects[0].contains_dir(&member));
+        assert!(projects[0].contains_dir(member.parent().unwrap()));
+        assert!(!projects[0].contains_dir(&link));
+    }
+}
@@ -0,0 +1,105 @@
+use std::str::FromStr as _;
+
+use anyhow::{Context as _, Result};
+use url::Url;
+
+use crate::crate_detail::CrateMetaData;
+use crate::crate_spec::CrateSpec;
+use crate::source_hash::git_dir_names;
+
+/// Crates which are never removed by cleaning commands. Entry is either crate
+/// spec such as `openssl-src`, `serde@^1` or `aws-sdk-*` or url of git
+/// repository which protects all of its checkouts and database
+#[derive(Default)]
+pub(crate) struct ProtectList {
+    specs: Vec<CrateSpec>,
+    git_dir_names: Vec<String>,
+}
+
+impl ProtectList {
+    /// parse protect list entries of config file
+    pub(crate) fn new(entries: &[String]) -> Result<Self> {
+        let mut protect_list = Self::default();
+        for entry in entries {
+            if let Some(url) = parse_git_url(entry)? {
+                protect_list.git_dir_names.extend(git_dir_names(&url));
+            } else {
+                protect_list.specs.push(CrateSpec::parse(entry)?);
+            }
+        }
+        Ok(protect_list)
+    }
+
+    /// check if crate is protected
+    pub(crate) fn is_protected(&self, crate_metadata: &CrateMetaData) -> bool {
+        self.specs.iter().any(|spec| spec.matches(crate_metadata))
+            || crate_metadata
+                .source()
+                .is_some_and(|source| self.git_dir_names.contains(source))
+    }
+}
+
+/// validate protect list entry before it is saved to config file
+pub(crate) fn validate_protect_entry(entry: &str) -> Result<()> {
+    if parse_git_url(entry)?.is_none() {
+        CrateSpec::parse(entry)?;
+    }
+    Ok(())
+}
+
+/// parse entry as git url. Return none when entry is not url
+fn parse_git_url(entry: &str) -> Result<Option<Url>> {
+    let value = entry.strip_prefix("git+").unwrap_or(entry);
+    if !value.contains("://") {
+        return Ok(None);
+    }
+    let url = Url::from_str(value).with_context(|| format!("failed to parse git url {entry}"))?;
+    Ok(Some(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use semver::Version;
+    use url::Url;
+
+    use super::ProtectList;
+    use crate::crate_detail::CrateMetaData;
+    use crate::source_hash::git_dir_names;
+
+    #[test]
+    fn protect_list_test() {
+        let protect_list = ProtectList::new(&[
+            "openssl-src".to_string(),
+            "aws-sdk-*@^1".to_string(),
+            "git+https://github.com/rust-lang/cargo.git".to_string(),
+        ])
+        .unwrap();
+        let registry_crate = |name: &str, version: Version| {
+            CrateMetaData::new(
+                name.to_string(),
+                Some(version),
+                Some("index.crates.io-1949cf8c6b5b557f".to_string()),
+            )
+        };
+        assert!(protect_list.is_protected(&registry_crate("openssl-src", Version::new(300, 0, 0))));
+        assert!(protect_list.is_protected(&registry_crate("aws-sdk-s3", Version::new(1, 2, 0))));
+        assert!(!protect_list.is_protected(&registry_crate("aws-sdk-s3", Version::new(0, 9, 0))));
+        assert!(!protect_list.is_protected(&registry_crate("openssl", Version::new(0, 10, 0))));
+
+        let cargo_url = Url::from_str("https://github.com/rust-lang/cargo").unwrap();
+        for dir_name in git_dir_names(&cargo_url) {
+            let checkout =
+                CrateMetaData::new("cargo-1a2b3c4".to_string(), None, Some(dir_name.clone()));
+            assert!(protect_list.is_protected(&checkout));
+        }
+        let other = CrateMetaData::new(
+            "cargo-1a2b3c4".to_string(),
+            None,
+            Some("cargo-0000000000000000".to_string()),
+        );
+        assert!(!protect_list.is_protected(&other));
+        assert!(ProtectList::new(&["serde@latest".to_string()]).is_err());
+    }
+}
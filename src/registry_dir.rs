@@ -1,11 +1,16 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context as _, Result};
 use owo_colors::OwoColorize as _;
+use semver::Version;
+use serde::Deserialize;
 
 use crate::crate_detail::{CrateDetail, CrateMetaData};
-use crate::utils::delete_folder;
+use crate::utils::{delete_folder, print_message};
+
+/// version of index cache file format written by cargo
+const INDEX_CACHE_VERSION: u8 = 3;
 
 /// Stores .cargo/registry cache & src information
 pub(crate) struct RegistryDir {
@@ -93,7 +98,7 @@ impl RegistryDir {
         }
 
         if dry_run {
-            println!(
+            print_message(format_args!(
                 "{} {} {} {}-{}",
                 "Dry run:".yellow(),
                 "Removed".red(),
@@ -106,10 +111,10 @@ impl RegistryDir {
                 crate_metadata
                     .version()
                     .context("failed to convert crate version")?,
-            );
+            ));
             Ok(true)
         } else if is_success {
-            println!(
+            print_message(format_args!(
                 "{} {} {}-{}",
                 "Removed".red(),
                 crate_metadata
@@ -121,10 +126,10 @@ impl RegistryDir {
                 crate_metadata
                     .version()
                     .context("failed to convert crate version")?,
-            );
+            ));
             Ok(true)
         } else {
-            println!(
+            print_message(format_args!(
                 "Failed to remove {} {}-{}",
                 crate_metadata.name(),
                 crate_metadata
@@ -135,38 +140,42 @@ impl RegistryDir {
                 crate_metadata
                     .version()
                     .context("failed to convert crate version")?,
-            );
+            ));
             Ok(false)
         }
     }
 
-    /// Remove list of crates
+    /// Remove list of crates and return list of crates which were removed
     pub(crate) fn remove_crate_list(
         &mut self,
         crate_detail: &CrateDetail,
         crate_metadata_list: &[CrateMetaData],
         dry_run: bool,
-    ) -> Result<(u64, usize)> {
-        let mut size_cleaned = 0;
-        let mut crate_removed = 0;
+    ) -> Result<Vec<CrateMetaData>> {
+        let mut removed_crates = Vec::new();
         for crate_metadata in crate_metadata_list {
             if self.remove_crate(crate_detail, crate_metadata, dry_run)? {
-                size_cleaned += crate_metadata.size();
-                crate_removed += 1;
+                removed_crates.push(crate_metadata.clone());
             }
         }
         for index_cache_dir in &self.index_cache_dir {
             let index = Path::new(&index_cache_dir);
             remove_empty_index_cache_dir(index, dry_run)?;
         }
-        Ok((size_cleaned, crate_removed))
+        Ok(removed_crates)
     }
 }
 
 /// determine crate index cache location and remove crate index cache
 fn remove_index_cache(path: &Path, crate_metadata: &CrateMetaData, dry_run: bool) -> Result<()> {
-    let mut crate_index_cache_location = path.to_path_buf();
-    let name = crate_metadata.name();
+    let crate_index_cache_location = index_cache_location(path, crate_metadata.name())?;
+    delete_folder(&crate_index_cache_location, dry_run)?;
+    Ok(())
+}
+
+/// determine location of crate index cache inside index .cache directory
+pub(crate) fn index_cache_location(index_cache_dir: &Path, name: &str) -> Result<PathBuf> {
+    let mut crate_index_cache_location = index_cache_dir.to_path_buf();
     // slice with `get` so a multi-byte crate name errors instead of panicking
     match name.len() {
         1 => {
@@ -197,8 +206,49 @@ fn remove_index_cache(path: &Path, crate_metadata: &CrateMetaData, dry_run: bool
             crate_index_cache_location.push(name);
         }
     }
-    delete_folder(&crate_index_cache_location, dry_run)?;
-    Ok(())
+    Ok(crate_index_cache_location)
+}
+
+/// Version entry of index cache file
+#[derive(Deserialize)]
+struct IndexCacheEntry {
+    cksum: String,
+}
+
+/// read checksum of crate version recorded in index cache. Return none when
+/// crate is not cached or cache file is of unsupported format
+pub(crate) fn index_cache_checksum(
+    index_cache_dir: &Path,
+    name: &str,
+    version: &Version,
+) -> Result<Option<String>> {
+    // cargo stores index cache of crate using lowercase name
+    let location = index_cache_location(index_cache_dir, &name.to_lowercase())?;
+    let Ok(content) = fs::read(location) else {
+        return Ok(None);
+    };
+    Ok(parse_index_cache_checksum(&content, version))
+}
+
+/// parse checksum of version from index cache content. Content starts with
+/// cache version, index format version and index version followed by null
+/// separated pair of version and its json entry
+fn parse_index_cache_checksum(content: &[u8], version: &Version) -> Option<String> {
+    let (&cache_version, rest) = content.split_first()?;
+    if cache_version != INDEX_CACHE_VERSION {
+        return None;
+    }
+    let mut parts = rest.get(4..)?.split(|byte| *byte == 0);
+    // skip index version
+    parts.next()?;
+    let version = version.to_string();
+    while let (Some(entry_version), Some(entry)) = (parts.next(), parts.next()) {
+        if entry_version == version.as_bytes() {
+            let entry: IndexCacheEntry = serde_json::from_slice(entry).ok()?;
+            return Some(entry.cksum);
+        }
+    }
+    None
 }
 
 /// check if any index cache folder is empty if it is removed directory. First
@@ -217,3 +267,37 @@ fn remove_empty_index_cache_dir(path: &Path, dry_run: bool) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::parse_index_cache_checksum;
+
+    #[test]
+    fn parse_index_cache_checksum_test() {
+        let mut content = vec![3, 2, 0, 0, 0];
+        content.extend_from_slice(b"etag\0");
+        for (version, cksum) in [("1.0.0", "aaaa"), ("1.0.1", "bbbb")] {
+            content.extend_from_slice(version.as_bytes());
+            content.push(0);
+            content.extend_from_slice(
+                format!(r#"{{"name":"serde","vers":"{version}","cksum":"{cksum}"}}"#).as_bytes(),
+            );
+            content.push(0);
+        }
+        assert_eq!(
+            parse_index_cache_checksum(&content, &Version::new(1, 0, 1)).as_deref(),
+            Some("bbbb")
+        );
+        assert_eq!(
+            parse_index_cache_checksum(&content, &Version::new(1, 0, 2)),
+            None
+        );
+        content[0] = 1;
+        assert_eq!(
+            parse_index_cache_checksum(&content, &Version::new(1, 0, 0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write as _;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::list_crate::RawLockFile;
+
+/// version of scan cache format. Cache with other version is discarded
+const SCAN_CACHE_VERSION: u32 = 3;
+
+/// Identity of file system entry. Entry is considered unchanged only when
+/// modification time, inode and length are all same
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Stamp {
+    modified: u128,
+    inode: u64,
+    len: u64,
+}
+
+impl Stamp {
+    /// create stamp from metadata of entry
+    pub(crate) fn new(metadata: &fs::Metadata) -> Self {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_nanos());
+        #[cfg(unix)]
+        let inode = metadata.ino();
+        #[cfg(not(unix))]
+        let inode = 0;
+        Self {
+            modified,
+            inode,
+            len: metadata.len(),
+        }
+    }
+}
+
+/// Cached value along with stamp of entry from which value was computed
+#[derive(Clone, Serialize, Deserialize)]
+struct Cached<T> {
+    stamp: Stamp,
+    value: T,
+}
+
+/// Sub directories and files named Cargo.lock or Cargo.toml of directory
+/// which is searched for projects
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct DirListing {
+    /// name of sub directories which are not symlink
+    pub(crate) dirs: Vec<String>,
+    /// name of Cargo.lock and Cargo.toml files
+    pub(crate) files: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ScanCacheData {
+    version: u32,
+    #[serde(default)]
+    lock_files: HashMap<PathBuf, Cached<RawLockFile>>,
+    #[serde(default)]
+    dir_listings: HashMap<PathBuf, Cached<DirListing>>,
+    #[serde(default)]
+    entry_sizes: HashMap<PathBuf, Cached<u64>>,
+}
+
+/// On disk cache of parsed Cargo.lock files, project directory listings and
+/// apparent size of extracted crate directories. Every value is keyed by path
+/// and reused only when stamp of entry is unchanged. Only values used by
+/// current run are saved so removed entries are dropped from cache. Size is
+/// cached only for crate directory which cargo does not modify after
+/// extraction since file can be modified in place without changing stamp of
+/// its directory
+pub(crate) struct ScanCache {
+    path: PathBuf,
+    enabled: bool,
+    previous: Mutex<ScanCacheData>,
+    current: Mutex<ScanCacheData>,
+}
+
+impl ScanCache {
+    /// load scan cache file. Cache is empty when it is disabled, missing or
+    /// cannot be read
+    pub(crate) fn load(path: &Path, enabled: bool) -> Self {
+        let previous = if enabled {
+            fs::read_to_string(path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<ScanCacheData>(&content).ok())
+                .filter(|data| data.version == SCAN_CACHE_VERSION)
+                .unwrap_or_default()
+        } else {
+            ScanCacheData::default()
+        };
+        Self {
+            path: path.to_path_buf(),
+            enabled,
+            previous: Mutex::new(previous),
+            current: Mutex::new(ScanCacheData {
+                version: SCAN_CACHE_VERSION,
+                ..ScanCacheData::default()
+            }),
+        }
+    }
+
+    /// return parsed Cargo.lock file or parse it with provided function
+    pub(crate) fn lock_file(
+        &self,
+        path: &Path,
+        stamp: Stamp,
+        parse: impl FnOnce() -> Result<RawLockFile>,
+    ) -> Result<RawLockFile> {
+        self.get_or_compute(path, stamp, |data| &mut data.lock_files, parse)
+    }
+
+    /// return listing of directory or read it with provided function
+    pub(crate) fn dir_listing(
+        &self,
+        path: &Path,
+        stamp: Stamp,
+        read: impl FnOnce() -> Result<DirListing>,
+    ) -> Result<DirListing> {
+        self.get_or_compute(path, stamp, |data| &mut data.dir_listings, read)
+    }
+
+    /// return apparent size of extracted crate directory or compute it with
+    /// provided function
+    pub(crate) fn entry_size(
+        &self,
+        path: &Path,
+        stamp: Stamp,
+        compute: impl FnOnce() -> Result<u64>,
+    ) -> Result<u64> {
+        self.get_or_compute(path, stamp, |data| &mut data.entry_sizes, compute)
+    }
+
+    /// save values used by current run to scan cache file. Content is written
+    /// to temporary file which is renamed over cache file so concurrent run
+    /// never reads partially written cache
+    pub(crate) fn save(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let content = serde_json::to_string(&*lock(&self.current))
+            .context("failed to convert scan cache to json")?;
+        let parent = self
+            .path
+            .parent()
+            .context("failed to get scan cache directory")?;
+        let mut temp_file = tempfile::NamedTempFile::new_in(parent)
+            .context("failed to create temporary scan cache file")?;
+        temp_file
+            .write_all(content.as_bytes())
+            .context("failed to write scan cache file")?;
+        temp_file
+            .persist(&self.path)
+            .context("failed to replace scan cache file")?;
+        Ok(())
+    }
+
+    /// reuse value of current or previous run when stamp matches otherwise
+    /// compute value. Value is recorded so it is saved for next run. Lock is
+    /// not held while computing since value can be computed in parallel
+    fn get_or_compute<T: Clone>(
+        &self,
+        path: &Path,
+        stamp: Stamp,
+        values: fn(&mut ScanCacheData) -> &mut HashMap<PathBuf, Cached<T>>,
+        compute: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        if !self.enabled {
+            return compute();
+        }
+        if let Some(cached) = values(&mut lock(&self.current))
+            .get(path)
+            .filter(|cached| cached.stamp == stamp)
+        {
+            return Ok(cached.value.clone());
+        }
+        let previous = values(&mut lock(&self.previous))
+            .remove(path)
+            .filter(|cached| cached.stamp == stamp);
+        let value = match previous {
+            Some(cached) => cached.value,
+            None => compute()?,
+        };
+        values(&mut lock(&self.current)).insert(
+            path.to_path_buf(),
+            Cached {
+                stamp,
+                value: value.clone(),
+            },
+        );
+        Ok(value)
+    }
+}
+
+fn lock(data: &Mutex<ScanCacheData>) -> MutexGuard<'_, ScanCacheData> {
+    data.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// remove scan cache file
+pub(crate) fn clear_scan_cache(path: &Path, dry_run: bool) -> Result<bool> {
+    let exists = path.exists();
+    if exists && !dry_run {
+        fs::remove_file(path).context("failed to remove scan cache file")?;
+    }
+    Ok(exists)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use super::{DirListing, ScanCache, Stamp};
+
+    #[test]
+    fn scan_cache_invalidation_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let cache_file = root.join("cache.json");
+        let project_dir = root.join("project");
+        let removed_dir = root.join("removed");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::create_dir_all(&removed_dir).unwrap();
+        let stamp = |path: &PathBuf| Stamp::new(&fs::metadata(path).unwrap());
+        let computed = Cell::new(0);
+        let listing = |file: &str| {
+            computed.set(computed.get() + 1);
+            Ok(DirListing {
+                dirs: Vec::new(),
+                files: vec![file.to_string()],
+            })
+        };
+        let files = |scan_cache: &ScanCache, path: &PathBuf, file| {
+            scan_cache
+                .dir_listing(path, stamp(path), || listing(file))
+                .unwrap()
+                .files
+        };
+
+        let scan_cache = ScanCache::load(&cache_file, true);
+        assert_eq!(
+            files(&scan_cache, &project_dir, "Cargo.lock"),
+            ["Cargo.lock"]
+        );
+        assert_eq!(
+            files(&scan_cache, &removed_dir, "Cargo.toml"),
+            ["Cargo.toml"]
+        );
+        scan_cache.save().unwrap();
+
+        // unchanged entry is reused and entry not used by run is dropped
+        let scan_cache = ScanCache::load(&cache_file, true);
+        assert_eq!(files(&scan_cache, &project_dir, "other"), ["Cargo.lock"]);
+        assert_eq!(computed.get(), 2);
+        scan_cache.save().unwrap();
+        let scan_cache = ScanCache::load(&cache_file, true);
+        assert_eq!(files(&scan_cache, &removed_dir, "other"), ["other"]);
+
+        // adding file changes stamp of directory so listing is read again
+        fs::write(project_dir.join("Cargo.toml"), "").unwrap();
+        assert_eq!(
+            files(&scan_cache, &project_dir, "Cargo.toml"),
+            ["Cargo.toml"]
+        );
+
+        // disabled cache always reads listing
+        let scan_cache = ScanCache::load(&cache_file, false);
+        assert_eq!(files(&scan_cache, &removed_dir, "other"), ["other"]);
+        assert_eq!(computed.get(), 5);
+        // only cache file is left after save
+        assert_eq!(fs::read_dir(root).unwrap().count(), 3);
+    }
+}
@@ -0,0 +1,181 @@
+use std::hash::Hasher;
+
+use rustc_stable_hash::StableSipHasher128;
+use url::Url;
+
+/// discriminant of cargo `SourceKind::Registry` which is hashed as part of
+/// source id
+const REGISTRY_KIND: isize = 2;
+
+/// discriminant of cargo `SourceKind::SparseRegistry` which is hashed as part
+/// of source id
+const SPARSE_REGISTRY_KIND: isize = 3;
+
+/// Hashing scheme used by cargo for naming cache directory. Cargo 1.85 changed
+/// hasher to stable hasher so directory created by older cargo uses legacy
+/// scheme
+#[derive(Clone, Copy)]
+enum HashScheme {
+    Legacy,
+    Stable,
+}
+
+impl HashScheme {
+    /// compute hash of value same as cargo `util::hex::hash_u64`
+    fn hash_u64(self, hash: impl Fn(&mut dyn Hasher)) -> u64 {
+        match self {
+            Self::Legacy => {
+                #[expect(deprecated)]
+                let mut hasher = std::hash::SipHasher::new();
+                hash(&mut hasher);
+                hasher.finish()
+            }
+            Self::Stable => {
+                let mut hasher = StableSipHasher128::new();
+                hash(&mut hasher);
+                Hasher::finish(&hasher)
+            }
+        }
+    }
+
+    /// compute short hash of value same as cargo `util::hex::short_hash`
+    fn short_hash(self, hash: impl Fn(&mut dyn Hasher)) -> String {
+        // cargo encodes little endian bytes of hash as hex
+        format!("{:016x}", self.hash_u64(hash).swap_bytes())
+    }
+}
+
+/// hash str same as `Hash` implementation of str which writes bytes followed
+/// by 0xff
+fn hash_str(hasher: &mut dyn Hasher, value: &str) {
+    hasher.write(value.as_bytes());
+    hasher.write_u8(0xff);
+}
+
+/// list directory names which can be used by cargo for index, cache and src
+/// directory of registry. Url is registry url of Cargo.lock without
+/// `registry+` prefix so sparse registry url is prefixed with `sparse+`
+pub(crate) fn registry_dir_names(url: &Url) -> Vec<String> {
+    let (kind, host) = match url.as_str().strip_prefix("sparse+") {
+        Some(sparse_url) => {
+            (
+                SPARSE_REGISTRY_KIND,
+                Url::parse(sparse_url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(String::from)),
+            )
+        }
+        None => (REGISTRY_KIND, url.host_str().map(String::from)),
+    };
+    let host = host.unwrap_or_default();
+    [HashScheme::Stable, HashScheme::Legacy]
+        .into_iter()
+        .map(|scheme| {
+            let hash = scheme.short_hash(|hasher| {
+                hasher.write_isize(kind);
+                hash_str(hasher, url.as_str());
+            });
+            format!("{host}-{hash}")
+        })
+        .collect()
+}
+
+/// list directory names which can be used by cargo for db and checkouts
+/// directory of git repository
+pub(crate) fn git_dir_names(url: &Url) -> Vec<String> {
+    let canonical_url = canonicalize_git_url(url);
+    let ident = canonical_url
+        .rsplit('/')
+        .next()
+        .filter(|ident| !ident.is_empty())
+        .unwrap_or("_empty")
+        .to_string();
+    [HashScheme::Stable, HashScheme::Legacy]
+        .into_iter()
+        .map(|scheme| {
+            let hash = scheme.short_hash(|hasher| hash_str(hasher, &canonical_url));
+            format!("{ident}-{hash}")
+        })
+        .collect()
+}
+
+/// list hashes of `rustc -vV` output which can be recorded by cargo as `rustc`
+/// field of fingerprint of unit built by that rustc
+pub(crate) fn rustc_hashes(verbose_version: &str) -> Vec<u64> {
+    [HashScheme::Stable, HashScheme::Legacy]
+        .into_iter()
+        .map(|scheme| scheme.hash_u64(|hasher| hash_str(hasher, verbose_version)))
+        .collect()
+}
+
+/// canonicalize git url same as cargo `CanonicalUrl`. Trailing slash and
+/// `.git` suffix is removed and github url is lower cased
+fn canonicalize_git_url(url: &Url) -> String {
+    let mut url = url.clone();
+    if let Some(path) = url.path().strip_suffix('/') {
+        let path = path.to_string();
+        url.set_path(&path);
+    }
+    if url.host_str() == Some("github.com") {
+        let after_scheme = &url[url::Position::AfterScheme..];
+        if let Ok(https_url) = Url::parse(&format!("https{after_scheme}")) {
+            url = https_url;
+        }
+        let path = url.path().to_lowercase();
+        url.set_path(&path);
+    }
+    if let Some(path) = url.path().strip_suffix(".git") {
+        let path = path.to_string();
+        url.set_path(&path);
+    }
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use url::Url;
+
+    use super::{git_dir_names, registry_dir_names, rustc_hashes};
+
+    #[test]
+    fn crates_io_registry_dir_names_test() {
+        let git_index = Url::from_str("https://github.com/rust-lang/crates.io-index").unwrap();
+        assert_eq!(
+            registry_dir_names(&git_index),
+            ["github.com-25cdd57fae9f0462", "github.com-1ecc6299db9ec823"]
+        );
+        let sparse_index = Url::from_str("sparse+https://index.crates.io/").unwrap();
+        assert_eq!(
+            registry_dir_names(&sparse_index),
+            [
+                "index.crates.io-1949cf8c6b5b557f",
+                "index.crates.io-6f17d22bba15001f"
+            ]
+        );
+    }
+
+    #[test]
+    fn git_dir_names_test() {
+        let url = Url::from_str("https://github.com/Rust-Lang/Cargo.git/").unwrap();
+        let same_url = Url::from_str("https://github.com/rust-lang/cargo").unwrap();
+        let names = git_dir_names(&url);
+        assert_eq!(names, git_dir_names(&same_url));
+        assert!(names.iter().all(|name| name.starts_with("cargo-")));
+        assert_eq!(names[0].len(), "cargo-".len() + 16);
+    }
+
+    #[test]
+    fn rustc_hashes_test() {
+        let verbose_version = "rustc 1.95.0 (59807616e 2026-04-14)
+binary: rustc
+commit-hash: 59807616e1fa2540724bfbac14d7976d7e4a3860
+commit-date: 2026-04-14
+host: x86_64-unknown-linux-gnu
+release: 1.95.0
+LLVM version: 22.1.2
+";
+        assert_eq!(rustc_hashes(verbose_version)[0], 7_458_672_600_737_419_911);
+    }
+}
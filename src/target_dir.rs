@@ -0,0 +1,432 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+use anyhow::{Context as _, Result};
+
+use crate::cargo_config::SourceConfig;
+use crate::source_hash::rustc_hashes;
+use crate::utils::get_size;
+
+/// Stores information about build profile directory such as debug or release
+pub(crate) struct Profile {
+    path: PathBuf,
+    last_build: Option<SystemTime>,
+}
+
+impl Profile {
+    /// read profile directory information
+    fn new(path: PathBuf) -> Result<Self> {
+        let mut last_build = None;
+        // fingerprint files of each unit are rewritten whenever unit is built
+        for unit in fs::read_dir(path.join(".fingerprint")).context("failed to read fingerprint")? {
+            let unit_path = unit?.path();
+            last_build = last_build.max(last_modified(&unit_path)?);
+        }
+        Ok(Self { path, last_build })
+    }
+
+    /// return path of profile directory
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// return last time when any unit of profile was built
+    pub(crate) fn last_build(&self) -> Option<SystemTime> {
+        self.last_build
+    }
+
+    /// check if cargo is currently building profile by checking build
+    /// directory lock held by cargo
+    pub(crate) fn is_building(&self) -> bool {
+        File::open(self.path.join(".cargo-lock"))
+            .is_ok_and(|file| matches!(file.try_lock(), Err(TryLockError::WouldBlock)))
+    }
+
+    /// list fingerprint units of profile which were built by rustc other than
+    /// rustc of provided hashes along with their artifacts present in deps and
+    /// build directory
+    pub(crate) fn artifacts_built_by_other_rustc(
+        &self,
+        rustc_hashes: &[u64],
+    ) -> Result<Vec<PathBuf>> {
+        let mut artifacts = Vec::new();
+        let fingerprint_dir = self.path.join(".fingerprint");
+        for entry in fs::read_dir(&fingerprint_dir).context("failed to read fingerprint dir")? {
+            let unit_path = entry?.path();
+            if !is_built_by_other_rustc(&unit_path, rustc_hashes)? {
+                continue;
+            }
+            let unit_name = unit_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .context("failed to get fingerprint unit name")?
+                .to_string();
+            let build_path = self.path.join("build").join(&unit_name);
+            if build_path.exists() {
+                artifacts.push(build_path);
+            }
+            let deps_dir = self.path.join("deps");
+            if deps_dir.is_dir() {
+                // dependency artifact are named after unit name and hash with different
+                // prefix and extension such as libname-hash.rlib or name-hash.d
+                let (name, hash) = unit_name.rsplit_once('-').unwrap_or((&unit_name, ""));
+                let artifact_stem = format!("{}-{hash}", name.replace('-', "_"));
+                for deps_entry in fs::read_dir(&deps_dir).context("failed to read deps dir")? {
+                    let deps_path = deps_entry?.path();
+                    let Some(file_name) = deps_path.file_name().and_then(|name| name.to_str())
+                    else {
+                        continue;
+                    };
+                    let file_stem = file_name.split('.').next().unwrap_or_default();
+                    if file_stem == artifact_stem
+                        || file_stem.strip_prefix("lib") == Some(artifact_stem.as_str())
+                    {
+                        artifacts.push(deps_path);
+                    }
+                }
+            }
+            artifacts.push(unit_path);
+        }
+        Ok(artifacts)
+    }
+}
+
+/// Stores information about target directory used by one or more project
+pub(crate) struct TargetDir {
+    path: PathBuf,
+    projects: Vec<PathBuf>,
+    size: u64,
+    profiles: Vec<Profile>,
+}
+
+impl TargetDir {
+    /// read target directory information
+    fn new(path: PathBuf, projects: Vec<PathBuf>) -> Result<Self> {
+        let size = get_size(&path).unwrap_or(0);
+        let mut profiles = Vec::new();
+        for entry in fs::read_dir(&path).context("failed to read target directory")? {
+            let entry_path = entry?.path();
+            if !entry_path.is_dir() {
+                continue;
+            }
+            if entry_path.join(".fingerprint").is_dir() {
+                profiles.push(Profile::new(entry_path)?);
+            } else {
+                // target triple directory contains profile directory inside it
+                for sub_entry in fs::read_dir(&entry_path).context("failed to read directory")? {
+                    let sub_entry_path = sub_entry?.path();
+                    if sub_entry_path.join(".fingerprint").is_dir() {
+                        profiles.push(Profile::new(sub_entry_path)?);
+                    }
+                }
+            }
+        }
+        profiles.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self {
+            path,
+            projects,
+            size,
+            profiles,
+        })
+    }
+
+    /// return path of target directory
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// return list of project which uses target directory
+    pub(crate) fn projects(&self) -> &[PathBuf] {
+        &self.projects
+    }
+
+    /// return size of target directory
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// return all profiles present in target directory
+    pub(crate) fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    /// return last time when any profile was built. Target directory modified
+    /// time is used if there is no profile
+    pub(crate) fn last_build(&self) -> Option<SystemTime> {
+        self.profiles
+            .iter()
+            .filter_map(Profile::last_build)
+            .max()
+            .or_else(|| last_modified(&self.path).ok().flatten())
+    }
+
+    /// return hashes of `rustc -vV` output of rustc used by projects of target
+    /// directory. Cargo records same hash in fingerprint of every unit
+    pub(crate) fn current_rustc_hashes(&self) -> Result<Vec<u64>> {
+        let mut hashes = Vec::new();
+        for project_dir in &self.projects {
+            hashes.extend(rustc_hashes(&rustc_verbose_version(project_dir)?));
+        }
+        Ok(hashes)
+    }
+}
+
+/// check if fingerprint unit was built by rustc other than rustc of provided
+/// hashes. Unit without readable fingerprint json file is not considered
+fn is_built_by_other_rustc(unit_path: &Path, rustc_hashes: &[u64]) -> Result<bool> {
+    let mut recorded_hashes = Vec::new();
+    for entry in fs::read_dir(unit_path).context("failed to read fingerprint unit")? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let rustc_hash = serde_json::from_str::<serde_json::Value>(&content)
+            .ok()
+            .and_then(|fingerprint| fingerprint.get("rustc")?.as_u64());
+        recorded_hashes.extend(rustc_hash);
+    }
+    Ok(!recorded_hashes.is_empty()
+        && recorded_hashes
+            .iter()
+            .all(|recorded_hash| !rustc_hashes.contains(recorded_hash)))
+}
+
+/// return `rustc -vV` output of rustc used by project. Rustc is run inside
+/// project directory so toolchain override of project is respected
+fn rustc_verbose_version(project_dir: &Path) -> Result<String> {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = Command::new(rustc)
+        .arg("-vV")
+        .current_dir(project_dir)
+        .output()
+        .context("failed to run rustc -vV command")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "rustc -vV failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout).context("failed to read rustc -vV output")
+}
+
+/// resolve target directory of project. `CARGO_TARGET_DIR`,
+/// `CARGO_BUILD_TARGET_DIR` and `build.target-dir` of cargo config are
+/// respected in same order as cargo, relative env value is resolved from
+/// project directory
+pub(crate) fn resolve_target_dir(project_dir: &Path, source_config: &SourceConfig) -> PathBuf {
+    if let Ok(target_dir) =
+        env::var("CARGO_TARGET_DIR").or_else(|_| env::var("CARGO_BUILD_TARGET_DIR"))
+    {
+        return project_dir.join(target_dir);
+    }
+    source_config
+        .target_dir()
+        .map_or_else(|| project_dir.join("target"), Path::to_path_buf)
+}
+
+/// list all existing target directories used by projects of provided Cargo.lock
+/// files. Projects sharing same target directory are grouped together
+pub(crate) fn list_target_dirs(
+    cargo_lock_files: &[PathBuf],
+    source_config: &SourceConfig,
+) -> Result<Vec<TargetDir>> {
+    let mut target_projects = BTreeMap::<PathBuf, Vec<PathBuf>>::new();
+    for lock_file in cargo_lock_files {
+        let Some(project_dir) = lock_file.parent() else {
+            continue;
+        };
+        let target_dir = resolve_target_dir(project_dir, &source_config.for_project(project_dir));
+        if target_dir.is_dir() {
+            target_projects
+                .entry(target_dir)
+                .or_default()
+                .push(project_dir.to_path_buf());
+        }
+    }
+    target_projects
+        .into_iter()
+        .map(|(path, projects)| TargetDir::new(path, projects))
+        .collect()
+}
+
+/// return latest modified time of path and its direct entries
+fn last_modified(path: &Path) -> Result<Option<SystemTime>> {
+    let metadata = path.symlink_metadata()?;
+    let mut last_modified = metadata.modified().ok();
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            last_modified = last_modified.max(entry?.metadata()?.modified().ok());
+        }
+    }
+    Ok(last_modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File, FileTimes};
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    use tempfile::TempDir;
+
+    use super::{list_target_dirs, rustc_verbose_version};
+    use crate::cargo_config::SourceConfig;
+    use crate::source_hash::rustc_hashes;
+
+    fn create_file(path: &Path, modified: SystemTime) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = File::create(path).unwrap();
+        file.set_times(FileTimes::new().set_modified(modified))
+            .unwrap();
+    }
+
+    fn set_modified(path: &Path, modified: SystemTime) {
+        File::open(path)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(modified))
+            .unwrap();
+    }
+
+    #[test]
+    fn list_target_dirs_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let project = root.join("project");
+        create_file(&project.join("Cargo.lock"), SystemTime::now());
+        let target = project.join("target");
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1001);
+        let new = SystemTime::UNIX_EPOCH + Duration::from_secs(2002);
+        create_file(&target.join("debug/.fingerprint/foo-0123/lib-foo"), new);
+        create_file(
+            &target.join("x86_64-unknown-linux-gnu/release/.fingerprint/foo-4567/lib-foo"),
+            old,
+        );
+        set_modified(&target.join("debug/.fingerprint/foo-0123"), new);
+        set_modified(
+            &target.join("x86_64-unknown-linux-gnu/release/.fingerprint/foo-4567"),
+            old,
+        );
+
+        let target_dirs =
+            list_target_dirs(&[project.join("Cargo.lock")], &SourceConfig::default()).unwrap();
+        assert_eq!(target_dirs.len(), 1);
+        let target_dir = &target_dirs[0];
+        assert_eq!(target_dir.projects(), std::slice::from_ref(&project));
+        assert_eq!(target_dir.profiles().len(), 2);
+        assert_eq!(target_dir.profiles()[0].last_build(), Some(new));
+        assert_eq!(target_dir.profiles()[1].last_build(), Some(old));
+        assert!(!target_dir.profiles()[0].is_building());
+    }
+
+    #[test]
+    fn artifacts_built_by_other_rustc_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let project = root.join("project");
+        create_file(&project.join("Cargo.lock"), SystemTime::now());
+        let profile = project.join("target/debug");
+        let now = SystemTime::now();
+        let write_fingerprint = |unit: &str, rustc: u64| {
+            create_file(
+                &profile.join(".fingerprint").join(unit).join("lib-foo-bar"),
+                now,
+            );
+            fs::write(
+                profile
+                    .join(".fingerprint")
+                    .join(unit)
+                    .join("lib-foo-bar.json"),
+                format!(r#"{{"rustc":{rustc},"features":"[]"}}"#),
+            )
+            .unwrap();
+        };
+        write_fingerprint("foo-bar-0123", 1);
+        write_fingerprint("foo-bar-4567", 2);
+        // unit without fingerprint json is kept
+        create_file(&profile.join(".fingerprint/baz-89ab/lib-baz"), now);
+        create_file(&profile.join("deps/libfoo_bar-0123.rlib"), now);
+        create_file(&profile.join("deps/foo_bar-0123.d"), now);
+        create_file(&profile.join("deps/libfoo_bar-4567.rlib"), now);
+        create_file(&profile.join("deps/libbaz-89ab.rlib"), now);
+        create_file(&profile.join("build/foo-bar-0123/output"), now);
+
+        let target_dirs =
+            list_target_dirs(&[project.join("Cargo.lock")], &SourceConfig::default()).unwrap();
+        let mut artifacts = target_dirs[0].profiles()[0]
+            .artifacts_built_by_other_rustc(&[2])
+            .unwrap();
+        artifacts.sort();
+        assert_eq!(
+            artifacts,
+            [
+                profile.join(".fingerprint/foo-bar-0123"),
+                profile.join("build/foo-bar-0123"),
+                profile.join("deps/foo_bar-0123.d"),
+                profile.join("deps/libfoo_bar-0123.rlib"),
+            ]
+        );
+    }
+
+    #[test]
+    fn config_target_dir_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let project = root.join("workspace/project");
+        create_file(&project.join("Cargo.lock"), SystemTime::now());
+        fs::create_dir_all(root.join("workspace/.cargo")).unwrap();
+        fs::write(
+            root.join("workspace/.cargo/config.toml"),
+            "[build]\ntarget-dir = \"shared-target\"\n",
+        )
+        .unwrap();
+        let target = root.join("workspace/shared-target");
+        create_file(
+            &target.join("debug/.fingerprint/foo-0123/lib-foo"),
+            SystemTime::now(),
+        );
+
+        let source_config = SourceConfig::load(&[]).unwrap();
+        let target_dirs = list_target_dirs(&[project.join("Cargo.lock")], &source_config).unwrap();
+        if std::env::var_os("CARGO_BUILD_TARGET_DIR").is_none()
+            && std::env::var_os("CARGO_TARGET_DIR").is_none()
+        {
+            assert_eq!(target_dirs.len(), 1);
+            assert_eq!(target_dirs[0].path(), target);
+        }
+    }
+
+    #[test]
+    fn current_rustc_hashes_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let project = root.join("project");
+        create_file(&project.join("Cargo.lock"), SystemTime::now());
+        let unit = project.join("target/debug/.fingerprint/foo-0123");
+        create_file(&unit.join("lib-foo"), SystemTime::now());
+        let current = rustc_hashes(&rustc_verbose_version(&project).unwrap());
+        fs::write(
+            unit.join("lib-foo.json"),
+            format!(r#"{{"rustc":{}}}"#, current[0]),
+        )
+        .unwrap();
+
+        let target_dirs =
+            list_target_dirs(&[project.join("Cargo.lock")], &SourceConfig::default()).unwrap();
+        let rustc_hashes = target_dirs[0].current_rustc_hashes().unwrap();
+        assert_eq!(rustc_hashes, current);
+        // unit built by current toolchain is kept
+        assert!(
+            target_dirs[0].profiles()[0]
+                .artifacts_built_by_other_rustc(&rustc_hashes)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
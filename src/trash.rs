@@ -0,0 +1,440 @@
+use std::fs;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::split_name_version;
+
+/// trash directory used by `delete_folder` when trash mode is enabled
+static TRASH_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// counter used to create unique trash entry id within same process
+static ENTRY_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// name of manifest file present inside trash directory
+const MANIFEST_FILE_NAME: &str = "manifest.jsonl";
+
+/// enable trash mode so removed files are moved to provided trash directory
+pub(crate) fn enable_trash(trash_dir: &Path) {
+    let _ = TRASH_DIR.set(trash_dir.to_path_buf());
+}
+
+/// return trash directory if trash mode is enabled
+pub(crate) fn enabled_trash_dir() -> Option<&'static Path> {
+    TRASH_DIR.get().map(PathBuf::as_path)
+}
+
+/// Store information about single file or directory moved to trash
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct TrashEntry {
+    id: String,
+    original_path: PathBuf,
+    trashed_at: u64,
+}
+
+impl TrashEntry {
+    /// return id of entry
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// return path from where entry was removed
+    pub(crate) fn original_path(&self) -> &Path {
+        &self.original_path
+    }
+
+    /// return time when entry was moved to trash
+    pub(crate) fn trashed_at(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(self.trashed_at)
+    }
+
+    /// return location of entry inside trash directory
+    pub(crate) fn trashed_path(&self, trash_dir: &Path) -> PathBuf {
+        let mut trashed_path = trash_dir.join(&self.id);
+        if let Some(file_name) = self.original_path.file_name() {
+            trashed_path.push(file_name);
+        }
+        trashed_path
+    }
+
+    /// check if entry matches id or crate name. Crate name is matched against
+    /// file name and its parent name so registry src, registry cache, index
+    /// cache, git checkout and git db entries can be matched using crate name
+    pub(crate) fn matches(&self, value: &str) -> bool {
+        if self.id == value {
+            return true;
+        }
+        let components = self
+            .original_path
+            .iter()
+            .rev()
+            .take(2)
+            .filter_map(|component| component.to_str());
+        for component in components {
+            let component = component.trim_end_matches(".crate");
+            if component == value
+                || split_name_version(component).is_ok_and(|(name, _)| name == value)
+                || component
+                    .rsplit_once('-')
+                    .is_some_and(|(name, hash)| name == value && is_short_hash(hash))
+            {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Stores all entries of trash directory
+pub(crate) struct Trash {
+    trash_dir: PathBuf,
+    entries: Vec<TrashEntry>,
+}
+
+impl Trash {
+    /// load trash manifest from trash directory
+    pub(crate) fn load(trash_dir: &Path) -> Result<Self> {
+        let manifest_file = trash_dir.join(MANIFEST_FILE_NAME);
+        let mut entries = Vec::new();
+        if manifest_file.exists() {
+            let content =
+                fs::read_to_string(&manifest_file).context("failed to read trash manifest")?;
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                entries.push(
+                    serde_json::from_str(line).context("failed to parse trash manifest entry")?,
+                );
+            }
+        }
+        Ok(Self {
+            trash_dir: trash_dir.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// return all entries present in trash
+    pub(crate) fn entries(&self) -> &[TrashEntry] {
+        &self.entries
+    }
+
+    /// return trash directory location
+    pub(crate) fn trash_dir(&self) -> &Path {
+        &self.trash_dir
+    }
+
+    /// restore entries which satisfy provided filter back to their original
+    /// location. Entries whose original location already exists are kept in
+    /// trash. Return list of restored entries
+    pub(crate) fn restore(
+        &mut self,
+        filter: impl Fn(&TrashEntry) -> bool,
+        dry_run: bool,
+    ) -> Result<Vec<TrashEntry>> {
+        let mut restored = Vec::new();
+        let mut remaining = Vec::new();
+        for entry in self.entries.drain(..) {
+            if !filter(&entry) {
+                remaining.push(entry);
+                continue;
+            }
+            let trashed_path = entry.trashed_path(&self.trash_dir);
+            if entry.original_path.exists() || !trashed_path.exists() {
+                remaining.push(entry);
+                continue;
+            }
+            if !dry_run {
+                if let Some(parent) = entry.original_path.parent() {
+                    fs::create_dir_all(parent)
+                        .context("failed to create parent directory of restored entry")?;
+                }
+                move_path(&trashed_path, &entry.original_path)
+                    .context("failed to move entry back from trash")?;
+                remove_entry_dir(&self.trash_dir, &entry)?;
+            }
+            restored.push(entry);
+        }
+        self.entries = remaining;
+        if dry_run {
+            self.entries.extend(restored.iter().cloned());
+        } else {
+            self.save()?;
+        }
+        Ok(restored)
+    }
+
+    /// permanently remove entries which satisfy provided filter from trash.
+    /// Return list of purged entries
+    pub(crate) fn purge(
+        &mut self,
+        filter: impl Fn(&TrashEntry) -> bool,
+        dry_run: bool,
+    ) -> Result<Vec<TrashEntry>> {
+        let (purged, remaining): (Vec<_>, Vec<_>) =
+            self.entries.drain(..).partition(|entry| filter(entry));
+        self.entries = remaining;
+        if dry_run {
+            self.entries.extend(purged.iter().cloned());
+        } else {
+            for entry in &purged {
+                remove_entry_dir(&self.trash_dir, entry)?;
+            }
+            self.save()?;
+        }
+        Ok(purged)
+    }
+
+    /// write all entries to manifest file
+    fn save(&self) -> Result<()> {
+        if !self.trash_dir.exists() {
+            return Ok(());
+        }
+        let mut content = String::new();
+        for entry in &self.entries {
+            content.push_str(
+                &serde_json::to_string(entry).context("failed to convert trash entry to json")?,
+            );
+            content.push('\n');
+        }
+        fs::write(self.trash_dir.join(MANIFEST_FILE_NAME), content)
+            .context("failed to write trash manifest")
+    }
+}
+
+/// move file or directory to trash directory and record it in manifest. Empty
+/// directories are removed directly since there is nothing to restore
+pub(crate) fn move_to_trash(trash_dir: &Path, path: &Path) -> Result<()> {
+    if path.is_dir() && fs::read_dir(path)?.next().is_none() {
+        fs::remove_dir(path)?;
+        return Ok(());
+    }
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let entry = TrashEntry {
+        id: format!(
+            "{}-{}-{}",
+            now.as_secs(),
+            std::process::id(),
+            ENTRY_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ),
+        original_path: path.to_path_buf(),
+        trashed_at: now.as_secs(),
+    };
+    let trashed_path = entry.trashed_path(trash_dir);
+    let entry_dir = trash_dir.join(&entry.id);
+    fs::create_dir_all(&entry_dir).context("failed to create trash entry directory")?;
+    move_path(path, &trashed_path)
+        .with_context(|| format!("failed to move {} to trash", path.display()))?;
+    let mut manifest = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(trash_dir.join(MANIFEST_FILE_NAME))
+        .context("failed to open trash manifest")?;
+    writeln!(
+        manifest,
+        "{}",
+        serde_json::to_string(&entry).context("failed to convert trash entry to json")?
+    )
+    .context("failed to write trash manifest")?;
+    Ok(())
+}
+
+/// move file or directory. Entry present in other file system such as target
+/// directory outside of cargo home is copied and then removed
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => copy_and_remove(from, to),
+        result => result,
+    }
+}
+
+/// copy entry to destination and remove it. Partial copy is removed when copy
+/// fails so entry is never lost
+fn copy_and_remove(from: &Path, to: &Path) -> io::Result<()> {
+    if let Err(err) = copy_path(from, to) {
+        let _ = remove_path(to);
+        return Err(err);
+    }
+    remove_path(from)
+}
+
+/// recursively copy entry without following symlink. Modify time is kept since
+/// it is used as last use time of restored entry
+fn copy_path(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = from.symlink_metadata()?;
+    if metadata.is_symlink() {
+        let target = fs::read_link(from)?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(target, to)?;
+        #[cfg(windows)]
+        if from.is_dir() {
+            std::os::windows::fs::symlink_dir(target, to)?;
+        } else {
+            std::os::windows::fs::symlink_file(target, to)?;
+        }
+        return Ok(());
+    }
+    if metadata.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    if let Ok(modified) = metadata.modified() {
+        // failure to keep modify time of directory is not fatal
+        let _ = fs::File::open(to).and_then(|file| file.set_modified(modified));
+    }
+    fs::set_permissions(to, metadata.permissions())
+}
+
+/// remove file, symlink or directory
+fn remove_path(path: &Path) -> io::Result<()> {
+    if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// remove directory holding trash entry
+fn remove_entry_dir(trash_dir: &Path, entry: &TrashEntry) -> Result<()> {
+    let entry_dir = trash_dir.join(&entry.id);
+    if entry_dir.exists() {
+        fs::remove_dir_all(&entry_dir).context("failed to remove trash entry directory")?;
+    }
+    Ok(())
+}
+
+/// check if value is 16 character hex hash used by cargo in git directory name
+fn is_short_hash(value: &str) -> bool {
+    value.len() == 16 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::{Trash, copy_and_remove, move_to_trash};
+
+    #[test]
+    fn move_and_restore_trash_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let trash_dir = root.join("trash");
+        let crate_dir = root.join("registry/src/index.crates.io-1949cf8c6b5b557f/serde-1.0.0");
+        fs::create_dir_all(&crate_dir).unwrap();
+        fs::write(crate_dir.join("lib.rs"), "fn main() {}").unwrap();
+        let index_cache = root.join("registry/index/index.crates.io-1949cf8c6b5b557f/.cache/se/rd");
+        fs::create_dir_all(&index_cache).unwrap();
+        fs::write(index_cache.join("serde"), "index").unwrap();
+
+        move_to_trash(&trash_dir, &crate_dir).unwrap();
+        move_to_trash(&trash_dir, &index_cache.join("serde")).unwrap();
+        assert!(!crate_dir.exists());
+        assert!(!index_cache.join("serde").exists());
+
+        let mut trash = Trash::load(&trash_dir).unwrap();
+        assert_eq!(trash.entries().len(), 2);
+        let restored = trash.restore(|entry| entry.matches("serde"), true).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(!crate_dir.exists());
+
+        let restored = trash
+            .restore(|entry| entry.matches("serde"), false)
+            .unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(crate_dir.join("lib.rs").exists());
+        assert!(index_cache.join("serde").exists());
+        assert!(Trash::load(&trash_dir).unwrap().entries().is_empty());
+    }
+
+    #[test]
+    fn purge_trash_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let trash_dir = root.join("trash");
+        let db_dir = root.join("git/db/bar-0123456789abcdef");
+        fs::create_dir_all(&db_dir).unwrap();
+        fs::write(db_dir.join("HEAD"), "ref").unwrap();
+        move_to_trash(&trash_dir, &db_dir).unwrap();
+
+        let mut trash = Trash::load(&trash_dir).unwrap();
+        assert!(trash.entries()[0].matches("bar"));
+        assert!(!trash.entries()[0].matches("ba"));
+        let trashed_path = trash.entries()[0].trashed_path(&trash_dir);
+        assert!(trashed_path.join("HEAD").exists());
+        let purged = trash.purge(|entry| entry.matches("bar"), false).unwrap();
+        assert_eq!(purged.len(), 1);
+        assert!(!trashed_path.exists());
+        assert!(Trash::load(&trash_dir).unwrap().entries().is_empty());
+    }
+
+    #[test]
+    fn empty_directory_is_not_trashed_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let trash_dir = root.join("trash");
+        let empty_dir = root.join("registry/index/idx/.cache");
+        fs::create_dir_all(&empty_dir).unwrap();
+        move_to_trash(&trash_dir, &empty_dir).unwrap();
+        assert!(!empty_dir.exists());
+        assert!(Trash::load(&trash_dir).unwrap().entries().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_and_remove_test() {
+        use std::fs::FileTimes;
+        use std::time::{Duration, SystemTime};
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let target_dir = root.join("project/target");
+        fs::create_dir_all(target_dir.join("debug/deps")).unwrap();
+        fs::write(target_dir.join("debug/deps/libserde.rlib"), "rlib").unwrap();
+        std::os::unix::fs::symlink("deps/libserde.rlib", target_dir.join("debug/libserde"))
+            .unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        fs::File::options()
+            .write(true)
+            .open(target_dir.join("debug/deps/libserde.rlib"))
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(modified))
+            .unwrap();
+
+        let trashed = root.join("trash/target");
+        fs::create_dir_all(root.join("trash")).unwrap();
+        copy_and_remove(&target_dir, &trashed).unwrap();
+        assert!(!target_dir.exists());
+        assert_eq!(
+            fs::read_to_string(trashed.join("debug/libserde")).unwrap(),
+            "rlib"
+        );
+        assert!(
+            fs::symlink_metadata(trashed.join("debug/libserde"))
+                .unwrap()
+                .is_symlink()
+        );
+        assert_eq!(
+            fs::metadata(trashed.join("debug/deps/libserde.rlib"))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            modified
+        );
+
+        // failed copy keeps source and removes partial copy
+        assert!(copy_and_remove(&trashed, &root.join("missing/target")).is_err());
+        assert!(trashed.exists());
+        assert!(!root.join("missing/target").exists());
+    }
+}
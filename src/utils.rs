@@ -1,13 +1,50 @@
 use std::collections::HashSet;
-use std::fs;
-#[cfg(unix)]
-use std::os::unix::fs::MetadataExt as _;
+use std::fmt::Display;
+use std::io::Write as _;
 use std::path::Path;
 use std::str::FromStr as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+use std::{fs, io};
 
 use anyhow::{Context as _, Result};
 use owo_colors::OwoColorize as _;
 use semver::Version;
+use sha2::{Digest as _, Sha256};
+
+use crate::dir_size::{walk_inode_handled_size, walk_size};
+use crate::trash::{enabled_trash_dir, move_to_trash};
+
+/// whether human readable messages are printed to stderr so stdout only
+/// contains machine readable output
+static MACHINE_READABLE_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// set whether machine readable output is requested
+pub(crate) fn set_machine_readable_output(value: bool) {
+    MACHINE_READABLE_OUTPUT.store(value, Ordering::Relaxed);
+}
+
+/// print human readable message line
+pub(crate) fn print_message(message: impl Display) {
+    if MACHINE_READABLE_OUTPUT.load(Ordering::Relaxed) {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
+    }
+}
+
+/// print prompt without new line so user can answer in same line
+pub(crate) fn print_prompt(prompt: &str) -> Result<()> {
+    if MACHINE_READABLE_OUTPUT.load(Ordering::Relaxed) {
+        eprint!("{prompt}");
+        io::stderr().flush().context("failed to flush error stream")
+    } else {
+        print!("{prompt}");
+        io::stdout()
+            .flush()
+            .context("failed to flush output stream")
+    }
+}
 
 /// split name and semver version part from crates full name
 pub(crate) fn split_name_version(full_name: &str) -> Result<(String, Version)> {
@@ -29,31 +66,23 @@ pub(crate) fn split_name_version(full_name: &str) -> Result<(String, Version)> {
     Ok((clear_name, version))
 }
 
-/// delete folder with folder path provided
+/// delete folder with folder path provided. When trash mode is enabled folder
+/// is moved to trash directory instead of getting deleted
 pub(crate) fn delete_folder(path: &Path, dry_run: bool) -> Result<()> {
-    if path.exists() {
-        if path.is_file() {
-            if dry_run {
-                println!(
-                    "{} {} {}",
-                    "Dry run:".yellow(),
-                    "Removed".red(),
-                    path.to_str().unwrap_or_default()
-                );
-            } else {
-                fs::remove_file(path)?;
-            }
-        } else if path.is_dir() {
-            if dry_run {
-                println!(
-                    "{} {} {}",
-                    "Dry run:".yellow(),
-                    "Removed".red(),
-                    path.to_str().unwrap_or_default()
-                );
-            } else {
-                fs::remove_dir_all(path)?;
-            }
+    if path.exists() && (path.is_file() || path.is_dir()) {
+        if dry_run {
+            print_message(format_args!(
+                "{} {} {}",
+                "Dry run:".yellow(),
+                "Removed".red(),
+                path.to_str().unwrap_or_default()
+            ));
+        } else if let Some(trash_dir) = enabled_trash_dir() {
+            move_to_trash(trash_dir, path)?;
+        } else if path.is_file() {
+            fs::remove_file(path)?;
+        } else {
+            fs::remove_dir_all(path)?;
         }
     }
     Ok(())
@@ -85,50 +114,101 @@ pub(crate) fn delete_index_cache(index_dir: &Path, dry_run: bool) -> Result<()>
     Ok(())
 }
 
-///  get size of path
+/// get size of path. Directory entries are walked in parallel
 pub(crate) fn get_size(path: &Path) -> Result<u64> {
-    let mut total_size = 0;
-    let metadata = path.metadata();
-    if let Ok(meta) = metadata {
-        if meta.is_dir() {
-            for entry in fs::read_dir(path)? {
-                let entry_path = entry?.path();
-                total_size += get_size(&entry_path)?;
-            }
-        } else if meta.is_file() {
-            total_size += meta.len();
-        }
-    }
-    Ok(total_size)
+    walk_size(path)
 }
 
-///  get accurate bin size
+/// get accurate bin size where hard linked file is counted only once
 pub(crate) fn get_inode_handled_size(path: &Path, inodes: &mut HashSet<u64>) -> Result<u64> {
-    let mut total_size = 0;
-    let metadata = path.metadata();
-    if let Ok(meta) = metadata {
-        if meta.is_dir() {
-            for entry in fs::read_dir(path)? {
-                let entry_path = entry?.path();
-                total_size += get_inode_handled_size(&entry_path, inodes)?;
-            }
-        } else if meta.is_file() {
-            let file_size = meta.len();
-            #[cfg(unix)]
-            {
-                let file_inode = meta.ino();
-                if !inodes.contains(&file_inode) {
-                    total_size += file_size;
-                    inodes.insert(file_inode);
-                }
-            }
-            #[cfg(not(unix))]
-            {
-                total_size += file_size;
-            }
+    walk_inode_handled_size(path, inodes)
+}
+
+/// compute SHA-256 hash of file content
+pub(crate) fn sha256_file(path: &Path) -> Result<[u8; 32]> {
+    let mut file =
+        fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(hasher.finalize().into())
+}
+
+/// get last used time of path from its modify time
+pub(crate) fn get_last_used(path: &Path) -> Option<SystemTime> {
+    let metadata = path.metadata().ok()?;
+    last_used_from_metadata(&metadata)
+}
+
+/// get last used time from metadata. Access time is not used since walking
+/// and hashing cache by cargo trim itself updates it, so modify time is used
+/// when cargo global cache tracker doesn't have last use time of entry
+pub(crate) fn last_used_from_metadata(metadata: &fs::Metadata) -> Option<SystemTime> {
+    metadata.modified().ok()
+}
+
+/// parse duration from human readable value such as `90d` or `6w`
+pub(crate) fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let unit_position = value
+        .find(|c: char| !c.is_ascii_digit())
+        .context("duration is missing unit, use one of s, m, h, d or w")?;
+    let (number, unit) = value.split_at(unit_position);
+    let number = number
+        .parse::<u64>()
+        .context("failed to parse number part of duration")?;
+    let seconds_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!("invalid duration unit {unit:?}, use one of s, m, h, d or w"),
+    };
+    let seconds = number
+        .checked_mul(seconds_per_unit)
+        .context("duration is too large")?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// parse size in bytes from human readable value such as `10GB` or `512MiB`
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    reason = "size is validated to be finite and in range before conversion"
+)]
+pub(crate) fn parse_size(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let unit_position = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_position);
+    let number = number
+        .parse::<f64>()
+        .context("failed to parse number part of size")?;
+    let bytes_per_unit: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000_u64.pow(2),
+        "gb" => 1000_u64.pow(3),
+        "tb" => 1000_u64.pow(4),
+        "kib" => 1024,
+        "mib" => 1024_u64.pow(2),
+        "gib" => 1024_u64.pow(3),
+        "tib" => 1024_u64.pow(4),
+        _ => {
+            anyhow::bail!(
+                "invalid size unit {unit:?}, use one of B, kB, MB, GB, TB or KiB, MiB, GiB, TiB"
+            )
         }
-    }
-    Ok(total_size)
+    };
+    let bytes = (number * bytes_per_unit as f64).round();
+    anyhow::ensure!(
+        bytes.is_finite() && bytes >= 0.0 && bytes < u64::MAX as f64,
+        "size is too large"
+    );
+    Ok(bytes as u64)
 }
 
 /// Convert size to pretty number
@@ -149,9 +229,11 @@ pub(crate) fn convert_pretty(num: u64) -> String {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use semver::Version;
 
-    use super::{convert_pretty, split_name_version};
+    use super::{convert_pretty, parse_duration, parse_size, split_name_version};
 
     #[test]
     fn split_name_version_test() {
@@ -213,6 +295,49 @@ mod tests {
         assert!(split_name_version("also-no-version").is_err());
     }
 
+    #[test]
+    fn parse_duration_test() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_mins(15));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_hours(2));
+        assert_eq!(
+            parse_duration("90d").unwrap(),
+            Duration::from_hours(90 * 24)
+        );
+        assert_eq!(
+            parse_duration("6w").unwrap(),
+            Duration::from_hours(6 * 7 * 24)
+        );
+    }
+
+    #[test]
+    fn parse_duration_invalid_is_error_test() {
+        assert!(parse_duration("90").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("10y").is_err());
+        assert!(parse_duration("1.5d").is_err());
+        assert!(parse_duration("99999999999999999999w").is_err());
+    }
+
+    #[test]
+    fn parse_size_test() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("20B").unwrap(), 20);
+        assert_eq!(parse_size("3kB").unwrap(), 3000);
+        assert_eq!(parse_size("10GB").unwrap(), 10_000_000_000);
+        assert_eq!(parse_size("1.5 GB").unwrap(), 1_500_000_000);
+        assert_eq!(parse_size("2MiB").unwrap(), 2_097_152);
+        assert_eq!(parse_size("1tib").unwrap(), 1_099_511_627_776);
+    }
+
+    #[test]
+    fn parse_size_invalid_is_error_test() {
+        assert!(parse_size("GB").is_err());
+        assert!(parse_size("10XB").is_err());
+        assert!(parse_size("1.2.3GB").is_err());
+        assert!(parse_size("99999999999TB").is_err());
+    }
+
     #[test]
     fn convert_pretty_test() {
         assert_eq!(convert_pretty(u64::MIN), "  0.000 B".to_string());
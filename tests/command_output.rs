@@ -20,6 +20,15 @@ fn command_line_success() {
     run_cargo_trim(&["help", "config"]);
     run_cargo_trim(&["help", "git"]);
     run_cargo_trim(&["help", "registry"]);
+    run_cargo_trim(&["help", "remove"]);
+    run_cargo_trim(&["help", "restore"]);
     run_cargo_trim(&["help", "set"]);
     run_cargo_trim(&["help", "unset"]);
+    run_cargo_trim(&["help", "trash"]);
+    run_cargo_trim(&["help", "target"]);
+    run_cargo_trim(&["help", "bin"]);
+    run_cargo_trim(&["help", "why"]);
+    run_cargo_trim(&["help", "cache"]);
+    run_cargo_trim(&["help", "dedup"]);
+    run_cargo_trim(&["help", "verify"]);
 }
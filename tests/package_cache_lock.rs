@@ -0,0 +1,47 @@
+#![expect(unused_crate_dependencies)]
+#![expect(clippy::tests_outside_test_module)]
+use std::fs::{self, File};
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn run_cargo_trim(cargo_home: &Path, args: &[&str]) -> Output {
+    let binary_program = env!("CARGO_BIN_EXE_cargo-trim");
+    Command::new(binary_program)
+        .env("CARGO_HOME", cargo_home)
+        .env("XDG_CONFIG_HOME", cargo_home.join("config"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// test destructive command is refused while package cache lock is held by other
+// process and succeed after lock is released
+#[test]
+fn package_cache_lock_held() {
+    let cargo_home =
+        std::env::temp_dir().join(format!("cargo-trim-lock-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&cargo_home);
+    let src_dir = cargo_home.join("registry").join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    let held_file = File::create(cargo_home.join(".package-cache")).unwrap();
+    held_file.lock().unwrap();
+
+    let output = run_cargo_trim(&cargo_home, &["--wipe", "src"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(".package-cache"));
+    assert!(src_dir.exists());
+
+    // dry run and query doesn't require lock
+    assert!(
+        run_cargo_trim(&cargo_home, &["--wipe", "src", "-n"])
+            .status
+            .success()
+    );
+    assert!(run_cargo_trim(&cargo_home, &["-q"]).status.success());
+
+    held_file.unlock().unwrap();
+    let output = run_cargo_trim(&cargo_home, &["--wipe", "src", "--no-wait"]);
+    assert!(output.status.success());
+    assert!(!src_dir.exists());
+    fs::remove_dir_all(&cargo_home).unwrap();
+}